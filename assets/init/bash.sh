@@ -0,0 +1,95 @@
+_opstrail_armed=0
+_opstrail_cmd=""
+_opstrail_cmd_start=""
+_opstrail_cmd_cwd=""
+
+_opstrail_return() {
+    return "$1"
+}
+
+# Prints the command of a trap from `trap -p`, which quotes it for the shell.
+_opstrail_trap_command() {
+    printf '%s' "$2"
+}
+
+_opstrail_prior_trap() {
+    local trap
+    trap=$(trap -p "$1")
+    [ -n "$trap" ] && eval "_opstrail_trap_command ${trap#trap }"
+}
+
+# The DEBUG trap also fires for each entry of PROMPT_COMMAND, for example when the
+# user presses enter on an empty line, so those are never taken for a command.
+_opstrail_in_prompt_command() {
+    local IFS=';'
+    local prompt=";${PROMPT_COMMAND[*]};"
+    prompt="${prompt//$'\n'/;}"
+    case "$prompt" in
+        *";$1;"*) return 0 ;;
+    esac
+    return 1
+}
+
+opstrail_preexec() {
+    local status=$? trap_status=0
+    if [ -n "${_opstrail_prior_debug:-}" ]; then
+        _opstrail_return "$status"
+        eval "$_opstrail_prior_debug"
+        trap_status=$?
+    fi
+
+    [ "$_opstrail_armed" -eq 1 ] || return "$trap_status"
+    [ -z "${COMP_LINE:-}" ] || return "$trap_status"
+    if _opstrail_in_prompt_command "$BASH_COMMAND"; then
+        _opstrail_armed=0
+        return "$trap_status"
+    fi
+
+    _opstrail_armed=0
+    _opstrail_cmd="$BASH_COMMAND"
+    _opstrail_cmd_start=$(opstrail_now)
+    _opstrail_cmd_cwd="$PWD"
+    return "$trap_status"
+}
+
+opstrail_precmd() {
+    local exit_code=$?
+    if [ -n "$_opstrail_cmd" ]; then
+        opstrail_log_command "$_opstrail_cmd" "$exit_code" "$_opstrail_cmd_start" "$(opstrail_now)" "$_opstrail_cmd_cwd"
+    fi
+    _opstrail_cmd=""
+    _opstrail_cmd_start=""
+    _opstrail_cmd_cwd=""
+}
+
+opstrail_arm() {
+    _opstrail_armed=1
+}
+
+opstrail_bash_exit() {
+    local status=$?
+    opstrail_exit
+    if [ -n "${_opstrail_prior_exit:-}" ]; then
+        _opstrail_return "$status"
+        eval "$_opstrail_prior_exit"
+    fi
+}
+
+# Keep any DEBUG or EXIT trap set before this script and run it from ours. Sourcing the
+# script twice must not chain our own handlers onto themselves.
+_opstrail_trap=$(_opstrail_prior_trap DEBUG)
+if [ "$_opstrail_trap" != "opstrail_preexec" ]; then
+    _opstrail_prior_debug="$_opstrail_trap"
+fi
+_opstrail_trap=$(_opstrail_prior_trap EXIT)
+if [ "$_opstrail_trap" != "opstrail_bash_exit" ]; then
+    _opstrail_prior_exit="$_opstrail_trap"
+fi
+unset _opstrail_trap
+
+trap opstrail_preexec DEBUG
+trap opstrail_bash_exit EXIT
+case ";$PROMPT_COMMAND;" in
+    *";opstrail_precmd;"*) ;;
+    *) PROMPT_COMMAND="opstrail_precmd${PROMPT_COMMAND:+;$PROMPT_COMMAND};opstrail_arm" ;;
+esac
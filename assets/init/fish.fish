@@ -0,0 +1,110 @@
+set -gx OPSTRAIL_TRAIL_PATH __OPSTRAIL_BIN__
+if not test -x "$OPSTRAIL_TRAIL_PATH"
+    set -gx OPSTRAIL_TRAIL_PATH trail
+end
+
+function _opstrail_trail
+    command $OPSTRAIL_TRAIL_PATH $argv
+end
+
+set -gx OPSTRAIL_SESSION_ID (_opstrail_trail log --session-start --print-session-id 2>/dev/null)
+
+function _opstrail_preexec --on-event fish_preexec
+    set -g _opstrail_cmd_cwd $PWD
+end
+
+function _opstrail_postexec --on-event fish_postexec
+    set -l exit_code $status
+    set -l cmd $argv[1]
+    set -l cwd $_opstrail_cmd_cwd
+    set -e _opstrail_cmd_cwd
+
+    switch "$cmd"
+        case '' 'trail*' 'opstrail*' '_opstrail*'
+            return
+    end
+    test -n "$cwd"; or set cwd $PWD
+
+    _opstrail_trail log --cmd "$cmd" --cwd "$cwd" --exit-code $exit_code \
+        --duration-ms $CMD_DURATION 2>/dev/null
+end
+
+function _opstrail_exit --on-event fish_exit
+    _opstrail_trail log --session-end 2>/dev/null
+end
+
+function _opstrail_check_auto_cd
+    set -l feature $argv[1]
+    set -l config_file $HOME/.opstrail/config.json
+
+    if not test -f $config_file
+        echo true
+        return
+    end
+
+    set -l result
+    if command -q jq
+        set result (jq -r ".auto_cd.$feature // true" $config_file 2>/dev/null)
+    else if command -q python3
+        set result (python3 -c "import json; print(json.load(open('$config_file')).get('auto_cd', {}).get('$feature', True))" 2>/dev/null | string lower)
+    else if grep -q "\"$feature\".*:.*false" $config_file 2>/dev/null
+        set result false
+    end
+
+    test -n "$result"; or set result true
+    echo $result
+end
+
+function _opstrail_resume_path
+    set -l json (_opstrail_trail resume --format ndjson 2>/dev/null); or return
+
+    if command -q jq
+        printf '%s\n' $json | jq -r '.cwd // empty' 2>/dev/null
+    else if command -q python3
+        printf '%s\n' $json | python3 -c 'import json, sys; print((json.load(sys.stdin) or {}).get("cwd") or "")' 2>/dev/null
+    else
+        printf '%s\n' $json | string match -rg '"cwd":"([^"]*)"'
+    end
+end
+
+function trail
+    set -l subcommand $argv[1]
+    set -e argv[1]
+
+    switch "$subcommand"
+        case back
+            if test (count $argv) -gt 0; and test (_opstrail_check_auto_cd back) = true
+                set -l when $argv[1]
+                set -l path (_opstrail_trail back $when 2>/dev/null)
+
+                if test $status -eq 0; and test -n "$path"; and test -d "$path"
+                    cd $path; or return 1
+                    echo "Jumped back $when to: $path"
+                else
+                    echo "No activity found for '$when'" >&2
+                    return 1
+                end
+            else
+                _opstrail_trail back $argv
+            end
+        case resume
+            _opstrail_trail resume $argv
+
+            if test (count $argv) -eq 0; and test (_opstrail_check_auto_cd resume) = true
+                set -l path (_opstrail_resume_path)
+
+                if test -n "$path"; and test -d "$path"
+                    echo ""
+                    read -l -P "Jump to this location? (y/n) " response
+                    if string match -qi 'y*' -- $response
+                        cd $path; or return 1
+                        echo "Resumed at: $path"
+                    end
+                end
+            end
+        case ''
+            _opstrail_trail
+        case '*'
+            _opstrail_trail $subcommand $argv
+    end
+end
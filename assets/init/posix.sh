@@ -0,0 +1,132 @@
+OPSTRAIL_TRAIL_PATH=__OPSTRAIL_BIN__
+if [ ! -x "$OPSTRAIL_TRAIL_PATH" ]; then
+    OPSTRAIL_TRAIL_PATH=trail
+fi
+export OPSTRAIL_TRAIL_PATH
+
+_opstrail_trail() {
+    command "$OPSTRAIL_TRAIL_PATH" "$@"
+}
+
+OPSTRAIL_SESSION_ID=$(_opstrail_trail log --session-start --print-session-id 2>/dev/null)
+export OPSTRAIL_SESSION_ID
+
+opstrail_now() {
+    if [ -n "$EPOCHREALTIME" ]; then
+        echo "$EPOCHREALTIME"
+    else
+        date +%s
+    fi
+}
+
+opstrail_log_command() {
+    local cmd="$1"
+    local exit_code="$2"
+    local started_at="$3"
+    local finished_at="$4"
+    local cwd="${5:-$PWD}"
+    case "$cmd" in
+        trail*|opstrail*|opstrail_*|_opstrail_*) return ;;
+    esac
+    if [ -n "$started_at" ]; then
+        _opstrail_trail log --cmd "$cmd" --cwd "$cwd" --exit-code "$exit_code" \
+            --started-at "$started_at" --finished-at "$finished_at" 2>/dev/null || true
+    else
+        _opstrail_trail log --cmd "$cmd" --cwd "$cwd" --exit-code "$exit_code" 2>/dev/null || true
+    fi
+}
+
+opstrail_exit() {
+    _opstrail_trail log --session-end 2>/dev/null || true
+}
+
+_opstrail_check_auto_cd() {
+    local feature="$1"
+    local config_file="$HOME/.opstrail/config.json"
+
+    if [ ! -f "$config_file" ]; then
+        echo "true"
+        return
+    fi
+
+    if command -v jq >/dev/null 2>&1; then
+        local result
+        result=$(jq -r ".auto_cd.$feature // true" "$config_file" 2>/dev/null)
+        echo "${result:-true}"
+    elif command -v python3 >/dev/null 2>&1; then
+        local result
+        result=$(python3 -c "import json; print(json.load(open(\"$config_file\")).get(\"auto_cd\", {}).get(\"$feature\", True))" 2>/dev/null | tr "[:upper:]" "[:lower:]")
+        echo "${result:-true}"
+    else
+        if grep -q "\"$feature\".*:.*false" "$config_file" 2>/dev/null; then
+            echo "false"
+        else
+            echo "true"
+        fi
+    fi
+}
+
+_opstrail_resume_path() {
+    local json
+    json=$(_opstrail_trail resume --format ndjson 2>/dev/null) || return
+
+    if command -v jq >/dev/null 2>&1; then
+        printf "%s\n" "$json" | jq -r ".cwd // empty" 2>/dev/null
+    elif command -v python3 >/dev/null 2>&1; then
+        printf "%s\n" "$json" | python3 -c "import json, sys; print((json.load(sys.stdin) or {}).get(\"cwd\") or \"\")" 2>/dev/null
+    else
+        printf "%s\n" "$json" | sed -n "s/.*\"cwd\":\"\([^\"]*\)\".*/\1/p"
+    fi
+}
+
+trail() {
+    local subcommand="$1"
+    [ $# -gt 0 ] && shift
+
+    case "$subcommand" in
+        back)
+            if [ $# -gt 0 ] && [ "$(_opstrail_check_auto_cd back)" = "true" ]; then
+                local when="$1"
+                local path
+                path=$(_opstrail_trail back "$when" 2>/dev/null)
+
+                if [ $? -eq 0 ] && [ -n "$path" ] && [ -d "$path" ]; then
+                    cd "$path" || return 1
+                    echo "Jumped back $when to: $path"
+                else
+                    echo "No activity found for '$when'" >&2
+                    return 1
+                fi
+            else
+                _opstrail_trail back "$@"
+            fi
+            ;;
+        resume)
+            _opstrail_trail resume "$@"
+
+            if [ $# -eq 0 ] && [ "$(_opstrail_check_auto_cd resume)" = "true" ]; then
+                local path
+                path=$(_opstrail_resume_path)
+
+                if [ -n "$path" ] && [ -d "$path" ]; then
+                    echo ""
+                    printf "Jump to this location? (y/n) "
+                    local response
+                    read -r response
+                    case "$response" in
+                        [Yy]*)
+                            cd "$path" || return 1
+                            echo "Resumed at: $path"
+                            ;;
+                    esac
+                fi
+            fi
+            ;;
+        "")
+            _opstrail_trail
+            ;;
+        *)
+            _opstrail_trail "$subcommand" "$@"
+            ;;
+    esac
+}
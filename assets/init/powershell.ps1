@@ -0,0 +1,122 @@
+$global:OpsTrail_TrailPath = __OPSTRAIL_BIN__
+if (-not (Test-Path $global:OpsTrail_TrailPath)) {
+    $global:OpsTrail_TrailPath = (Get-Command trail -CommandType Application -ErrorAction SilentlyContinue |
+        Select-Object -First 1).Source
+}
+
+$env:OPSTRAIL_SESSION_ID = & $global:OpsTrail_TrailPath log --session-start --print-session-id 2>$null
+
+$global:OpsTrail_LastHistoryId = (Get-History -Count 1 -ErrorAction SilentlyContinue).Id
+
+function global:OpsTrail-LogCommand {
+    param([bool]$succeeded, $nativeExitCode)
+
+    $lastCmd = Get-History -Count 1 -ErrorAction SilentlyContinue
+    if ($lastCmd -and $lastCmd.Id -ne $global:OpsTrail_LastHistoryId) {
+        $global:OpsTrail_LastHistoryId = $lastCmd.Id
+        $cmd = $lastCmd.CommandLine
+        if ($cmd -like "trail *" -or $cmd -like "opstrail *" -or $cmd -like "*OpsTrail*") {
+            return
+        }
+        $cwd = $PWD.Path
+        $exitCode = if ($succeeded) { 0 } elseif ($nativeExitCode) { $nativeExitCode } else { 1 }
+        $startedAt = $lastCmd.StartExecutionTime.ToUniversalTime().ToString("o")
+        $finishedAt = $lastCmd.EndExecutionTime.ToUniversalTime().ToString("o")
+        & $global:OpsTrail_TrailPath log --cmd "$cmd" --cwd "$cwd" --exit-code $exitCode --started-at $startedAt --finished-at $finishedAt 2>$null
+    }
+}
+
+if (-not $global:OpsTrail_OriginalPrompt) {
+    $global:OpsTrail_OriginalPrompt = $function:prompt
+
+    function global:prompt {
+        $succeeded = $?
+        $nativeExitCode = $global:LASTEXITCODE
+        OpsTrail-LogCommand $succeeded $nativeExitCode
+        $global:LASTEXITCODE = $nativeExitCode
+        & $global:OpsTrail_OriginalPrompt
+    }
+
+    Register-EngineEvent PowerShell.Exiting -Action {
+        try {
+            & $global:OpsTrail_TrailPath log --session-end 2>$null
+        } catch {}
+    } | Out-Null
+}
+
+function global:OpsTrail-AutoCd {
+    param([string]$feature)
+
+    $configPath = [IO.Path]::Combine($HOME, ".opstrail", "config.json")
+    if (Test-Path $configPath) {
+        try {
+            $config = Get-Content $configPath -Raw | ConvertFrom-Json
+            if ($null -ne $config.auto_cd -and $null -ne $config.auto_cd.$feature) {
+                return [bool]$config.auto_cd.$feature
+            }
+        } catch {}
+    }
+    return $true
+}
+
+function global:OpsTrail-Back {
+    param([string]$when)
+
+    $path = & $global:OpsTrail_TrailPath back $when 2>$null
+    if ($LASTEXITCODE -eq 0 -and $path -and (Test-Path $path)) {
+        Set-Location $path
+        Write-Host "Jumped back $when to: $path" -ForegroundColor Green
+    } else {
+        Write-Host "No activity found for '$when'" -ForegroundColor Red
+    }
+}
+
+function global:OpsTrail-Resume {
+    & $global:OpsTrail_TrailPath resume
+    $resume = & $global:OpsTrail_TrailPath resume --format ndjson 2>$null | ConvertFrom-Json
+    $path = $resume.cwd
+
+    if ($path -and (Test-Path $path)) {
+        Write-Host ""
+        $response = Read-Host "Jump to this location? (y/n)"
+        if ($response -eq 'y' -or $response -eq 'Y') {
+            Set-Location $path
+            Write-Host "Resumed at: $path" -ForegroundColor Green
+        }
+    }
+}
+
+function global:trail-back {
+    param([string]$when = "30m")
+    OpsTrail-Back $when
+}
+
+function global:trail-resume {
+    OpsTrail-Resume
+}
+
+function global:trail {
+    param(
+        [Parameter(Position=0)]
+        [string]$subcommand,
+
+        [Parameter(Position=1, ValueFromRemainingArguments=$true)]
+        [string[]]$remainingArgs
+    )
+
+    if ($subcommand -eq "back" -and $remainingArgs.Count -gt 0 -and (OpsTrail-AutoCd "back")) {
+        OpsTrail-Back $remainingArgs[0]
+        return
+    }
+
+    if ($subcommand -eq "resume" -and $remainingArgs.Count -eq 0 -and (OpsTrail-AutoCd "resume")) {
+        OpsTrail-Resume
+        return
+    }
+
+    if ($subcommand) {
+        & $global:OpsTrail_TrailPath $subcommand @remainingArgs
+    } else {
+        & $global:OpsTrail_TrailPath
+    }
+}
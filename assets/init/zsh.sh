@@ -0,0 +1,27 @@
+zmodload zsh/datetime 2>/dev/null
+_opstrail_cmd=""
+_opstrail_cmd_start=""
+_opstrail_cmd_cwd=""
+
+opstrail_preexec() {
+    _opstrail_cmd="$1"
+    _opstrail_cmd_start=$(opstrail_now)
+    _opstrail_cmd_cwd="$PWD"
+}
+
+opstrail_precmd() {
+    local exit_code=$?
+    if [ -n "$_opstrail_cmd" ]; then
+        opstrail_log_command "$_opstrail_cmd" "$exit_code" "$_opstrail_cmd_start" "$(opstrail_now)" "$_opstrail_cmd_cwd"
+    fi
+    _opstrail_cmd=""
+    _opstrail_cmd_start=""
+    _opstrail_cmd_cwd=""
+}
+
+autoload -Uz add-zsh-hook
+add-zsh-hook preexec opstrail_preexec
+add-zsh-hook zshexit opstrail_exit
+if (( ! ${precmd_functions[(I)opstrail_precmd]} )); then
+    precmd_functions=(opstrail_precmd $precmd_functions)
+fi
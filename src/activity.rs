@@ -0,0 +1,269 @@
+use crate::events::{Event, EventType};
+use crate::utils;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+pub fn idle_gap(
+    last_activity: DateTime<Utc>,
+    resumed_at: DateTime<Utc>,
+    idle_timeout_minutes: u64,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    if idle_timeout_minutes == 0 {
+        return None;
+    }
+
+    let timeout = Duration::minutes(idle_timeout_minutes as i64);
+    if resumed_at - last_activity >= timeout {
+        Some((last_activity, resumed_at))
+    } else {
+        None
+    }
+}
+
+pub fn idle_spans(events: &[Event]) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut ordered: Vec<&Event> = events
+        .iter()
+        .filter(|e| matches!(e.event_type, EventType::IdleStart | EventType::IdleEnd))
+        .collect();
+    ordered.sort_by_key(|e| e.timestamp);
+
+    let mut open: HashMap<Option<&str>, DateTime<Utc>> = HashMap::new();
+    let mut spans = Vec::new();
+
+    for event in ordered {
+        let session = event.session_id.as_deref();
+        match event.event_type {
+            EventType::IdleStart => {
+                open.entry(session).or_insert(event.timestamp);
+            }
+            EventType::IdleEnd => {
+                if let Some(start) = open.remove(&session) {
+                    spans.push((start, event.timestamp));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    spans
+}
+
+pub fn paused_spans(events: &[Event], now: DateTime<Utc>) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut ordered: Vec<&Event> = events
+        .iter()
+        .filter(|e| matches!(e.event_type, EventType::Paused { .. } | EventType::Unpaused))
+        .collect();
+    ordered.sort_by_key(|e| e.timestamp);
+
+    let mut open: HashMap<Option<&str>, (DateTime<Utc>, DateTime<Utc>)> = HashMap::new();
+    let mut spans = Vec::new();
+
+    for event in ordered {
+        let session = event.session_id.as_deref();
+        match event.event_type {
+            EventType::Paused { until } => {
+                let end = until.unwrap_or(now).min(now);
+                open.entry(session)
+                    .and_modify(|(_, open_end)| *open_end = (*open_end).max(end))
+                    .or_insert((event.timestamp, end));
+            }
+            EventType::Unpaused => {
+                if let Some((start, end)) = open.remove(&session) {
+                    spans.push((start, end.min(event.timestamp)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    spans.extend(open.into_values());
+    spans.sort();
+    spans
+}
+
+pub fn total_paused(events: &[Event], now: DateTime<Utc>) -> Duration {
+    paused_spans(events, now)
+        .into_iter()
+        .fold(Duration::zero(), |total, (start, end)| {
+            total + (end - start).max(Duration::zero())
+        })
+}
+
+pub fn total_idle(events: &[Event]) -> Duration {
+    idle_spans(events)
+        .into_iter()
+        .fold(Duration::zero(), |total, (start, end)| {
+            total + (end - start)
+        })
+}
+
+#[derive(Debug, Default)]
+pub struct ActiveTime {
+    pub total: Duration,
+    pub by_project: HashMap<String, Duration>,
+    pub by_day: BTreeMap<NaiveDate, Duration>,
+    pub by_session: HashMap<String, Duration>,
+}
+
+impl ActiveTime {
+    fn add(&mut self, event: &Event, start: DateTime<Utc>, end: DateTime<Utc>) {
+        if end <= start {
+            return;
+        }
+
+        let span = end - start;
+        self.total += span;
+
+        if let Some(ref project) = event.project {
+            *self
+                .by_project
+                .entry(project.clone())
+                .or_insert(Duration::zero()) += span;
+        }
+        if let Some(ref session_id) = event.session_id {
+            *self
+                .by_session
+                .entry(session_id.clone())
+                .or_insert(Duration::zero()) += span;
+        }
+
+        let mut cursor = start;
+        while cursor < end {
+            let day = cursor.with_timezone(&Local).date_naive();
+            let day_end = utils::local_day_start(day.succ_opt().unwrap()).min(end);
+            *self.by_day.entry(day).or_insert(Duration::zero()) += day_end - cursor;
+            cursor = day_end;
+        }
+    }
+}
+
+pub fn active_time(events: &[Event], idle_timeout_minutes: u64) -> ActiveTime {
+    let mut sessions: HashMap<Option<&str>, Vec<&Event>> = HashMap::new();
+    for event in events.iter().filter(|e| !e.has_synthesized_time()) {
+        sessions
+            .entry(event.session_id.as_deref())
+            .or_default()
+            .push(event);
+    }
+
+    let timeout = Duration::minutes(idle_timeout_minutes as i64);
+    let mut active = ActiveTime::default();
+
+    for session_events in sessions.values_mut() {
+        session_events.sort_by_key(|e| e.timestamp);
+
+        for pair in session_events.windows(2) {
+            let (prev, next) = (pair[0], pair[1]);
+
+            if let Some(started_at) = command_start(next) {
+                active.add(next, started_at.max(prev.timestamp), next.timestamp);
+            }
+
+            if matches!(
+                prev.event_type,
+                EventType::SessionEnd | EventType::IdleStart | EventType::Paused { .. }
+            ) {
+                continue;
+            }
+
+            let gap_end = command_start(next)
+                .unwrap_or(next.timestamp)
+                .max(prev.timestamp);
+            if idle_timeout_minutes > 0 && gap_end - prev.timestamp > timeout {
+                continue;
+            }
+
+            active.add(prev, prev.timestamp, gap_end);
+        }
+    }
+
+    active
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkBlock {
+    pub session_id: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub commands: usize,
+    pub notes: usize,
+    pub projects: BTreeSet<String>,
+}
+
+impl WorkBlock {
+    fn new(event: &Event, start: DateTime<Utc>) -> Self {
+        Self {
+            session_id: event.session_id.clone(),
+            start,
+            end: start,
+            commands: 0,
+            notes: 0,
+            projects: BTreeSet::new(),
+        }
+    }
+
+    fn extend(&mut self, event: &Event) {
+        self.end = self.end.max(event.timestamp);
+        match event.event_type {
+            EventType::Command { .. } => self.commands += 1,
+            EventType::Note { .. } => self.notes += 1,
+            _ => {}
+        }
+        if let Some(ref project) = event.project {
+            self.projects.insert(project.clone());
+        }
+    }
+}
+
+pub fn work_blocks(events: &[Event], idle_timeout_minutes: u64) -> Vec<WorkBlock> {
+    let mut sessions: HashMap<Option<&str>, Vec<&Event>> = HashMap::new();
+    for event in events.iter().filter(|e| !e.has_synthesized_time()) {
+        sessions
+            .entry(event.session_id.as_deref())
+            .or_default()
+            .push(event);
+    }
+
+    let timeout = Duration::minutes(idle_timeout_minutes as i64);
+    let mut blocks = Vec::new();
+
+    for session_events in sessions.values_mut() {
+        session_events.sort_by_key(|e| e.timestamp);
+        let mut current: Option<WorkBlock> = None;
+
+        for event in session_events.iter() {
+            match event.event_type {
+                EventType::SessionEnd | EventType::IdleStart | EventType::Paused { .. } => {
+                    blocks.extend(current.take());
+                    continue;
+                }
+                EventType::SessionStart | EventType::IdleEnd | EventType::Unpaused => continue,
+                _ => {}
+            }
+
+            let start = command_start(event).unwrap_or(event.timestamp);
+            if current
+                .as_ref()
+                .is_some_and(|block| idle_timeout_minutes > 0 && start - block.end > timeout)
+            {
+                blocks.extend(current.take());
+            }
+
+            current
+                .get_or_insert_with(|| WorkBlock::new(event, start))
+                .extend(event);
+        }
+
+        blocks.extend(current);
+    }
+
+    blocks.sort_by_key(|block| block.start);
+    blocks
+}
+
+fn command_start(event: &Event) -> Option<DateTime<Utc>> {
+    match event.event_type {
+        EventType::Command { started_at, .. } => started_at.filter(|&s| s < event.timestamp),
+        _ => None,
+    }
+}
@@ -1,4 +1,6 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
 #[derive(Parser, Debug)]
 #[command(name = "trail")]
@@ -18,28 +20,58 @@ pub enum Command {
     Stats(StatsArgs),
     Timeline(TimelineArgs),
     Note(NoteArgs),
-    Resume,
-    Today,
-    Sessions,
-    Projects,
+    Resume(OutputArgs),
+    Today(OutputArgs),
+    Sessions(OutputArgs),
+    Projects(OutputArgs),
     Config(ConfigArgs),
+    /// Permanently delete whole months of history older than --keep-days. No archive is kept
     Prune(PruneArgs),
+    Redact(RedactArgs),
+    Pause(PauseArgs),
+    Unpause(UnpauseArgs),
+    Forget(ForgetArgs),
+    Migrate(MigrateArgs),
+    Doctor(DoctorArgs),
+    Import(ImportArgs),
+    Export(ExportArgs),
+    Init(InitArgs),
+    Daemon(DaemonArgs),
 }
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LogArgs {
     #[arg(long)]
     pub cmd: Option<String>,
 
+    #[arg(long, allow_negative_numbers = true)]
+    pub exit_code: Option<i32>,
+
+    #[arg(long)]
+    pub started_at: Option<String>,
+
+    #[arg(long)]
+    pub finished_at: Option<String>,
+
+    #[arg(long)]
+    pub duration_ms: Option<u64>,
+
     #[arg(long)]
     pub cwd: Option<String>,
 
     #[arg(long)]
     pub project: Option<String>,
 
+    #[arg(long, env = "OPSTRAIL_SESSION_ID")]
+    pub session: Option<String>,
+
     #[arg(long)]
     pub session_start: bool,
 
+    #[arg(long)]
+    pub print_session_id: bool,
+
     #[arg(long)]
     pub session_end: bool,
 
@@ -59,14 +91,11 @@ pub struct BackArgs {
 pub struct SearchArgs {
     pub query: String,
 
-    #[arg(long)]
-    pub today: bool,
-
-    #[arg(long)]
-    pub project: Option<String>,
+    #[command(flatten)]
+    pub filter: FilterArgs,
 
-    #[arg(long)]
-    pub date: Option<String>,
+    #[command(flatten)]
+    pub output: OutputArgs,
 }
 
 #[derive(Args, Debug)]
@@ -82,6 +111,9 @@ pub struct StatsArgs {
 
     #[arg(long)]
     pub month: bool,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
 }
 
 #[derive(Args, Debug)]
@@ -97,11 +129,31 @@ pub struct TimelineArgs {
 
     #[arg(long, short = 'n', default_value = "50")]
     pub limit: usize,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct OutputArgs {
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Ndjson,
 }
 
 #[derive(Args, Debug)]
 pub struct NoteArgs {
     pub text: String,
+
+    #[arg(long, env = "OPSTRAIL_SESSION_ID")]
+    pub session: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -114,6 +166,7 @@ pub struct ConfigArgs {
 pub enum ConfigSubcommand {
     Show,
     Set(ConfigSetArgs),
+    Ignore(IgnoreArgs),
 }
 
 #[derive(Args, Debug)]
@@ -122,6 +175,44 @@ pub struct ConfigSetArgs {
     pub value: String,
 }
 
+#[derive(Args, Debug)]
+pub struct IgnoreArgs {
+    #[command(subcommand)]
+    pub action: IgnoreAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IgnoreAction {
+    List,
+    Add(IgnoreRuleArgs),
+    Remove(IgnoreRuleArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct IgnoreRuleArgs {
+    #[arg(value_enum)]
+    pub kind: IgnoreKind,
+
+    #[arg(allow_hyphen_values = true)]
+    pub pattern: String,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum IgnoreKind {
+    Command,
+    #[value(alias = "dir")]
+    Directory,
+}
+
+impl fmt::Display for IgnoreKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IgnoreKind::Command => write!(f, "command"),
+            IgnoreKind::Directory => write!(f, "directory"),
+        }
+    }
+}
+
 #[derive(Args, Debug)]
 pub struct PruneArgs {
     #[arg(long, default_value = "90")]
@@ -130,3 +221,166 @@ pub struct PruneArgs {
     #[arg(long)]
     pub dry_run: bool,
 }
+
+#[derive(Args, Debug)]
+pub struct RedactArgs {
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct MigrateArgs {
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct DoctorArgs {
+    #[arg(long)]
+    pub fix: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ImportArgs {
+    #[arg(value_enum)]
+    pub format: HistoryFormat,
+
+    pub path: Option<String>,
+
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum HistoryFormat {
+    Bash,
+    Zsh,
+    Fish,
+    #[value(alias = "pwsh")]
+    Powershell,
+}
+
+impl fmt::Display for HistoryFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryFormat::Bash => write!(f, "bash"),
+            HistoryFormat::Zsh => write!(f, "zsh"),
+            HistoryFormat::Fish => write!(f, "fish"),
+            HistoryFormat::Powershell => write!(f, "powershell"),
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    #[arg(value_enum)]
+    pub format: ExportFormat,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    #[arg(long, short = 'o')]
+    pub output: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    #[value(alias = "md")]
+    Markdown,
+    Ics,
+}
+
+#[derive(Args, Debug)]
+pub struct PauseArgs {
+    pub duration: Option<String>,
+
+    #[arg(long)]
+    pub terminal: bool,
+
+    #[arg(long, env = "OPSTRAIL_SESSION_ID")]
+    pub session: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct UnpauseArgs {
+    #[arg(long, env = "OPSTRAIL_SESSION_ID")]
+    pub session: Option<String>,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct FilterArgs {
+    #[arg(long)]
+    pub today: bool,
+
+    #[arg(long)]
+    pub date: Option<String>,
+
+    #[arg(long)]
+    pub from: Option<String>,
+
+    #[arg(long)]
+    pub to: Option<String>,
+
+    #[arg(long)]
+    pub project: Option<String>,
+
+    #[arg(long)]
+    pub session: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ForgetArgs {
+    pub query: Option<String>,
+
+    #[arg(long)]
+    pub id: Vec<String>,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    #[arg(long)]
+    pub dry_run: bool,
+
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct InitArgs {
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    #[value(alias = "pwsh")]
+    Powershell,
+    #[value(alias = "nu")]
+    Nushell,
+}
+
+impl fmt::Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shell::Bash => write!(f, "bash"),
+            Shell::Zsh => write!(f, "zsh"),
+            Shell::Fish => write!(f, "fish"),
+            Shell::Powershell => write!(f, "powershell"),
+            Shell::Nushell => write!(f, "nushell"),
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct DaemonArgs {
+    #[arg(long)]
+    pub stop: bool,
+
+    #[arg(long)]
+    pub status: bool,
+}
@@ -1,5 +1,10 @@
-use crate::cli::{ConfigArgs, ConfigSetArgs, ConfigSubcommand};
-use anyhow::{anyhow, Context, Result};
+use crate::cli::{
+    ConfigArgs, ConfigSetArgs, ConfigSubcommand, IgnoreAction, IgnoreArgs, IgnoreKind,
+    IgnoreRuleArgs,
+};
+use crate::detect::ProjectDetector;
+use crate::ignore::IgnoreRules;
+use anyhow::{Context, Result, anyhow};
 use colored::*;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -10,25 +15,76 @@ use std::cell::RefCell;
 
 #[cfg(test)]
 thread_local! {
-    static TEST_TIMELINE_PATH: RefCell<Option<PathBuf>> = RefCell::new(None);
+    static TEST_DATA_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
 }
 
+/// Settings from `~/.opstrail/config.json`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Minutes without activity after which time stops counting as active.
     pub idle_timeout_minutes: u64,
+    /// Whether the ProjWarp detector is used.
     pub enable_projwarp_integration: bool,
+    /// Which commands change the shell's directory.
     #[serde(default)]
     pub auto_cd: AutoCdConfig,
+    /// Project detectors, tried in order.
+    #[serde(default = "ProjectDetector::all")]
+    pub project_detectors: Vec<ProjectDetector>,
+    /// Secret redaction before events are written.
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    /// Commands and directories that are never logged.
+    #[serde(default)]
+    pub ignore: IgnoreConfig,
 }
 
+/// Whether `trail back` and `trail resume` change the shell's directory.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutoCdConfig {
+    /// `trail back` changes directory.
     #[serde(default = "default_true")]
     pub back: bool,
+    /// `trail resume` changes directory.
     #[serde(default = "default_true")]
     pub resume: bool,
 }
 
+/// Settings for redacting secrets from commands and notes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// Whether redaction runs at all.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Extra regular expressions to redact, on top of the built-in rules.
+    #[serde(default)]
+    pub rules: Vec<String>,
+}
+
+/// Commands and directories that are never logged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IgnoreConfig {
+    /// Skip commands typed with a leading space.
+    #[serde(default = "default_true")]
+    pub leading_space: bool,
+    /// Commands to skip, as globs or as regular expressions prefixed with `re:`.
+    #[serde(default)]
+    pub commands: Vec<String>,
+    /// Directories whose activity is skipped, as globs or as regular expressions
+    /// prefixed with `re:`.
+    #[serde(default)]
+    pub directories: Vec<String>,
+}
+
+impl IgnoreConfig {
+    fn rules_mut(&mut self, kind: IgnoreKind) -> &mut Vec<String> {
+        match kind {
+            IgnoreKind::Command => &mut self.commands,
+            IgnoreKind::Directory => &mut self.directories,
+        }
+    }
+}
+
 fn default_true() -> bool {
     true
 }
@@ -42,17 +98,40 @@ impl Default for AutoCdConfig {
     }
 }
 
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl Default for IgnoreConfig {
+    fn default() -> Self {
+        Self {
+            leading_space: true,
+            commands: Vec::new(),
+            directories: Vec::new(),
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             idle_timeout_minutes: 10,
             enable_projwarp_integration: true,
             auto_cd: AutoCdConfig::default(),
+            project_detectors: ProjectDetector::all(),
+            redaction: RedactionConfig::default(),
+            ignore: IgnoreConfig::default(),
         }
     }
 }
 
 impl Config {
+    /// Reads the config file, writing the defaults first if it does not exist.
     pub fn load() -> Result<Self> {
         let path = Self::config_path()?;
 
@@ -68,6 +147,7 @@ impl Config {
         }
     }
 
+    /// Writes the config file, creating `~/.opstrail` if needed.
     pub fn save(&self) -> Result<()> {
         let path = Self::config_path()?;
         let dir = path.parent().unwrap();
@@ -77,34 +157,44 @@ impl Config {
         Ok(())
     }
 
+    /// `~/.opstrail/config.json`.
     pub fn config_path() -> Result<PathBuf> {
-        let home = dirs::home_dir().context("Could not determine home directory")?;
-        Ok(home.join(".opstrail").join("config.json"))
+        Ok(Self::data_dir()?.join("config.json"))
     }
 
+    /// `~/.opstrail`, where every file OpsTrail writes lives.
     pub fn data_dir() -> Result<PathBuf> {
+        #[cfg(test)]
+        {
+            let override_dir = TEST_DATA_DIR.with(|d| d.borrow().clone());
+            if let Some(dir) = override_dir {
+                return Ok(dir);
+            }
+        }
         let home = dirs::home_dir().context("Could not determine home directory")?;
         Ok(home.join(".opstrail"))
     }
 
+    /// Directory of monthly timeline segments, `~/.opstrail/timeline/`.
+    pub fn timeline_dir() -> Result<PathBuf> {
+        Ok(Self::data_dir()?.join("timeline"))
+    }
+
+    /// The single-file timeline written by earlier versions, `~/.opstrail/timeline.jsonl`.
+    /// Its events move into [`Config::timeline_dir`] the next time the timeline is opened.
     pub fn timeline_path() -> Result<PathBuf> {
-        #[cfg(test)]
-        {
-            let override_path = TEST_TIMELINE_PATH.with(|p| p.borrow().clone());
-            if let Some(path) = override_path {
-                return Ok(path);
-            }
-        }
         Ok(Self::data_dir()?.join("timeline.jsonl"))
     }
 
+    /// `~/.opstrail/state.json`, the open sessions and pauses.
     pub fn state_path() -> Result<PathBuf> {
         Ok(Self::data_dir()?.join("state.json"))
     }
 
+    /// Points [`Config::data_dir`] at `dir` for the rest of the calling thread.
     #[cfg(test)]
-    pub fn timeline_path_override_for_test(path: PathBuf) {
-        TEST_TIMELINE_PATH.with(|p| *p.borrow_mut() = Some(path));
+    pub fn data_dir_override_for_test(dir: PathBuf) {
+        TEST_DATA_DIR.with(|d| *d.borrow_mut() = Some(dir));
     }
 }
 
@@ -112,6 +202,7 @@ pub fn handle_config_command(args: ConfigArgs) -> Result<()> {
     match args.subcommand {
         ConfigSubcommand::Show => show_config(),
         ConfigSubcommand::Set(set_args) => set_config(set_args),
+        ConfigSubcommand::Ignore(ignore_args) => ignore_config(ignore_args),
     }
 }
 
@@ -142,6 +233,39 @@ fn show_config() -> Result<()> {
         "auto_cd.resume",
         config.auto_cd.resume.to_string().yellow()
     );
+    println!(
+        "  {:<35} {}",
+        "project_detectors",
+        detector_list(&config.project_detectors).yellow()
+    );
+    println!(
+        "  {:<35} {}",
+        "redaction.enabled",
+        config.redaction.enabled.to_string().yellow()
+    );
+    println!(
+        "  {:<35} {}",
+        "redaction.rules",
+        config.redaction.rules.len().to_string().yellow()
+    );
+    for rule in &config.redaction.rules {
+        println!("    {}", rule.dimmed());
+    }
+    println!(
+        "  {:<35} {}",
+        "ignore.leading_space",
+        config.ignore.leading_space.to_string().yellow()
+    );
+    println!(
+        "  {:<35} {}",
+        "ignore.commands",
+        config.ignore.commands.len().to_string().yellow()
+    );
+    println!(
+        "  {:<35} {}",
+        "ignore.directories",
+        config.ignore.directories.len().to_string().yellow()
+    );
 
     Ok(())
 }
@@ -178,9 +302,32 @@ fn set_config(args: ConfigSetArgs) -> Result<()> {
                 .map_err(|_| anyhow!("Value must be true or false"))?;
             config.auto_cd.resume = val;
         }
+        "redaction.enabled" => {
+            let val: bool = args
+                .value
+                .parse()
+                .map_err(|_| anyhow!("Value must be true or false"))?;
+            config.redaction.enabled = val;
+        }
+        "ignore.leading_space" => {
+            let val: bool = args
+                .value
+                .parse()
+                .map_err(|_| anyhow!("Value must be true or false"))?;
+            config.ignore.leading_space = val;
+        }
+        "project_detectors" => {
+            let val = args
+                .value
+                .split(',')
+                .filter(|name| !name.trim().is_empty())
+                .map(str::parse)
+                .collect::<Result<Vec<ProjectDetector>>>()?;
+            config.project_detectors = val;
+        }
         _ => {
             return Err(anyhow!(
-                "Unknown config key: {}. Valid keys: idle_timeout_minutes, enable_projwarp_integration, auto_cd.back, auto_cd.resume",
+                "Unknown config key: {}. Valid keys: idle_timeout_minutes, enable_projwarp_integration, auto_cd.back, auto_cd.resume, project_detectors, redaction.enabled, ignore.leading_space",
                 args.key
             ));
         }
@@ -191,3 +338,66 @@ fn set_config(args: ConfigSetArgs) -> Result<()> {
 
     Ok(())
 }
+
+fn detector_list(detectors: &[ProjectDetector]) -> String {
+    if detectors.is_empty() {
+        return "none".to_string();
+    }
+
+    detectors
+        .iter()
+        .map(|d| d.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn ignore_config(args: IgnoreArgs) -> Result<()> {
+    let mut config = Config::load()?;
+
+    match args.action {
+        IgnoreAction::List => {
+            println!("{}", "Ignore Rules".bold().cyan());
+            println!();
+            println!(
+                "  {:<35} {}",
+                "leading_space",
+                config.ignore.leading_space.to_string().yellow()
+            );
+            for (label, rules) in [
+                ("commands", &config.ignore.commands),
+                ("directories", &config.ignore.directories),
+            ] {
+                println!();
+                println!("  {}", label.bold());
+                if rules.is_empty() {
+                    println!("    {}", "(none)".dimmed());
+                }
+                for rule in rules {
+                    println!("    {}", rule.yellow());
+                }
+            }
+            return Ok(());
+        }
+        IgnoreAction::Add(IgnoreRuleArgs { kind, pattern }) => {
+            let rules = config.ignore.rules_mut(kind);
+            if rules.contains(&pattern) {
+                println!("Rule already present: {}", pattern.yellow());
+                return Ok(());
+            }
+            rules.push(pattern.clone());
+            IgnoreRules::new(&config.ignore)?;
+            println!("Added {} ignore rule: {}", kind, pattern.green());
+        }
+        IgnoreAction::Remove(IgnoreRuleArgs { kind, pattern }) => {
+            let rules = config.ignore.rules_mut(kind);
+            let before = rules.len();
+            rules.retain(|rule| *rule != pattern);
+            if rules.len() == before {
+                return Err(anyhow!("No {} ignore rule matches: {}", kind, pattern));
+            }
+            println!("Removed {} ignore rule: {}", kind, pattern.yellow());
+        }
+    }
+
+    config.save()
+}
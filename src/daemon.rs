@@ -0,0 +1,434 @@
+use crate::cli::{DaemonArgs, LogArgs};
+use crate::config::Config;
+use crate::events::Event;
+use crate::logger::Recorder;
+use crate::projwarp::ProjWarp;
+use crate::session::{SessionChange, SessionManager, SessionState};
+use crate::storage;
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Local, Utc};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::time::Duration;
+use std::time::SystemTime;
+
+const MAX_BATCH: usize = 64;
+#[cfg(unix)]
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+#[cfg(unix)]
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// One line of JSON sent to the daemon socket.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    Log(LogArgs),
+    /// Re-read `state.json`, sent by `trail pause` and `trail unpause`.
+    Reload,
+    Status,
+    Stop,
+}
+
+/// The daemon's one-line JSON reply.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Response {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Status {
+    pub pid: u32,
+    pub version: String,
+    pub started: DateTime<Utc>,
+    pub logged: usize,
+    pub pending: usize,
+}
+
+/// Config, ProjWarp mappings and session state held in memory, with the events and
+/// session changes not yet written to disk.
+pub struct Daemon {
+    recorder: Recorder,
+    state: SessionState,
+    events: Vec<Event>,
+    changes: Vec<SessionChange>,
+    config_stamp: Option<(SystemTime, u64)>,
+    projwarp_stamp: Option<(SystemTime, u64)>,
+    state_stamp: Option<(SystemTime, u64)>,
+    started: DateTime<Utc>,
+    logged: usize,
+}
+
+impl Daemon {
+    pub fn new(recorder: Recorder, state: SessionState, started: DateTime<Utc>) -> Self {
+        Self {
+            recorder,
+            state,
+            events: Vec::new(),
+            changes: Vec::new(),
+            config_stamp: None,
+            projwarp_stamp: None,
+            state_stamp: None,
+            started,
+            logged: 0,
+        }
+    }
+
+    pub fn load() -> Result<Self> {
+        let recorder = Recorder::new(Config::load()?)?;
+        let state_stamp = stamp(&Config::state_path()?);
+        let mut daemon = Self::new(recorder, SessionManager::state()?, Utc::now());
+        daemon.state_stamp = state_stamp;
+        daemon.config_stamp = stamp(&Config::config_path()?);
+        daemon.projwarp_stamp = ProjWarp::config_path().and_then(|path| stamp(&path));
+        Ok(daemon)
+    }
+
+    pub fn handle(&mut self, request: Request) -> Response {
+        let result = match request {
+            Request::Log(args) => self.refresh().and_then(|_| {
+                let session_id = self.log(args, Utc::now())?;
+                if self.events.len() >= MAX_BATCH {
+                    self.flush()?;
+                }
+                Ok(Response {
+                    session_id,
+                    ..Response::default()
+                })
+            }),
+            Request::Reload => {
+                self.state_stamp = None;
+                self.refresh().map(|_| Response::default())
+            }
+            Request::Status => Ok(Response {
+                status: Some(self.status()),
+                ..Response::default()
+            }),
+            Request::Stop => self.flush().map(|_| Response::default()),
+        };
+
+        result.unwrap_or_else(|e| Response {
+            error: Some(e.to_string()),
+            ..Response::default()
+        })
+    }
+
+    /// Records one `trail log` call in memory and returns its session id.
+    pub fn log(&mut self, args: LogArgs, now: DateTime<Utc>) -> Result<Option<String>> {
+        let Some(pending) = self.recorder.prepare(args)? else {
+            return Ok(None);
+        };
+
+        let logged = self.recorder.commit(pending, &self.state, now);
+        for change in &logged.changes {
+            self.state.apply(change);
+        }
+        self.events.extend(logged.events);
+        self.changes.extend(logged.changes);
+
+        Ok(Some(logged.session_id))
+    }
+
+    /// Replaces the in-memory state with `state`, then replays the changes not yet saved.
+    pub fn rebase(&mut self, state: SessionState) {
+        self.state = state;
+        for change in &self.changes {
+            self.state.apply(change);
+        }
+    }
+
+    #[cfg(test)]
+    pub fn state(&self) -> &SessionState {
+        &self.state
+    }
+
+    #[cfg(test)]
+    pub fn pending(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn status(&self) -> Status {
+        Status {
+            pid: std::process::id(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            started: self.started,
+            logged: self.logged,
+            pending: self.events.len(),
+        }
+    }
+
+    /// Appends the pending events to the timeline and merges the pending session changes
+    /// into `state.json`.
+    pub fn flush(&mut self) -> Result<()> {
+        if !self.events.is_empty() {
+            storage::open()?.append_all(&self.events)?;
+            self.logged += self.events.len();
+            self.events.clear();
+        }
+
+        if !self.changes.is_empty() {
+            self.state = SessionManager::apply(&self.changes)?;
+            self.changes.clear();
+            // Another writer may have saved between our write and a later stamp, so
+            // read the file again on the next event rather than trusting the stamp.
+            self.state_stamp = None;
+        }
+
+        Ok(())
+    }
+
+    // `trail config`, `trail pause` and ProjWarp write their files directly, so pick up
+    // their changes before each event. Files are compared by modification time and length.
+    // A quick pause and resume can land within one mtime tick and leave the length of
+    // `state.json` unchanged, so `trail pause` and `trail unpause` also send a `Reload`.
+    fn refresh(&mut self) -> Result<()> {
+        let config_stamp = stamp(&Config::config_path()?);
+        if config_stamp != self.config_stamp {
+            self.recorder = Recorder::new(Config::load()?)?;
+            self.config_stamp = config_stamp;
+        }
+
+        let projwarp_stamp = ProjWarp::config_path().and_then(|path| stamp(&path));
+        if projwarp_stamp != self.projwarp_stamp {
+            ProjWarp::reload();
+            self.projwarp_stamp = projwarp_stamp;
+        }
+
+        // Stamp before reading, so a write that lands in between is seen next time.
+        let state_stamp = stamp(&Config::state_path()?);
+        if state_stamp.is_none() || state_stamp != self.state_stamp {
+            self.rebase(SessionManager::state()?);
+            self.state_stamp = state_stamp;
+        }
+        Ok(())
+    }
+}
+
+pub fn socket_path() -> Result<PathBuf> {
+    Ok(Config::data_dir()?.join("daemon.sock"))
+}
+
+/// Sends a `trail log` call to the daemon. Returns `None` when no daemon is listening,
+/// so the caller writes the event itself. Once the request is sent the daemon may have
+/// recorded it, so a missing or unreadable reply is an error rather than a fallback.
+pub fn send_log(args: &LogArgs) -> Result<Option<Response>> {
+    request(&socket_path()?, &Request::Log(args.clone()))
+        .context("The daemon may already have recorded the event, so it was not written again")
+}
+
+/// Tells a running daemon that `state.json` changed. Does nothing when no daemon answers.
+pub fn notify_state_changed() {
+    if let Ok(path) = socket_path() {
+        let _ = exchange(&path, &Request::Reload);
+    }
+}
+
+pub fn daemon(args: DaemonArgs) -> Result<()> {
+    let path = socket_path()?;
+
+    if args.stop {
+        match request(&path, &Request::Stop)? {
+            Some(_) => println!("Daemon {}.", "stopped".yellow()),
+            None => println!("Daemon is not running."),
+        }
+        return Ok(());
+    }
+
+    if args.status {
+        match request(&path, &Request::Status)?.and_then(|response| response.status) {
+            Some(status) => print_status(&path, &status),
+            None => println!("Daemon is not running."),
+        }
+        return Ok(());
+    }
+
+    serve(&path)
+}
+
+fn print_status(path: &Path, status: &Status) {
+    println!(
+        "Daemon {} (pid {}, trail {})",
+        "running".green(),
+        status.pid,
+        status.version
+    );
+    println!("  Socket:  {}", path.display());
+    println!(
+        "  Started: {}",
+        status
+            .started
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S")
+    );
+    println!(
+        "  Logged:  {} events ({} pending)",
+        status.logged, status.pending
+    );
+}
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+fn request(path: &Path, request: &Request) -> Result<Option<Response>> {
+    match exchange(path, request)? {
+        Some(response) => reported(response).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn reported(response: Response) -> Result<Response> {
+    match response.error {
+        Some(error) => Err(anyhow!("Daemon: {}", error)),
+        None => Ok(response),
+    }
+}
+
+#[cfg(unix)]
+fn exchange(path: &Path, request: &Request) -> Result<Option<Response>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let Ok(mut stream) = UnixStream::connect(path) else {
+        return Ok(None);
+    };
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    if stream.write_all(line.as_bytes()).is_err() {
+        return Ok(None);
+    }
+
+    let mut reply = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut reply)
+        .context("No reply from the daemon")?;
+    let response: Response = serde_json::from_str(&reply)
+        .map_err(|e| anyhow!("Invalid reply from the daemon: {}", e))?;
+
+    Ok(Some(response))
+}
+
+#[cfg(not(unix))]
+fn exchange(_path: &Path, _request: &Request) -> Result<Option<Response>> {
+    Ok(None)
+}
+
+#[cfg(unix)]
+fn serve(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixListener;
+    use std::sync::{Arc, Mutex, PoisonError};
+    use std::thread;
+
+    if path.exists() {
+        if request(path, &Request::Status)?.is_some() {
+            return Err(anyhow!(
+                "A daemon is already running. Stop it with: trail daemon --stop"
+            ));
+        }
+        fs::remove_file(path)?;
+    }
+    // The socket is created with the umask's mode and only narrowed after `bind`, so
+    // keep other users out of the directory before it exists.
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+        fs::set_permissions(parent, fs::Permissions::from_mode(0o700))?;
+    }
+
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to listen on {}", path.display()))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+
+    let daemon = Arc::new(Mutex::new(Daemon::load()?));
+    {
+        let daemon = Arc::clone(&daemon);
+        thread::spawn(move || {
+            loop {
+                thread::sleep(FLUSH_INTERVAL);
+                let mut daemon = daemon.lock().unwrap_or_else(PoisonError::into_inner);
+                if let Err(e) = daemon.flush() {
+                    eprintln!("{} {}", "error:".red(), e);
+                }
+            }
+        });
+    }
+
+    println!("Listening on {}", path.display());
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("{} {}", "error:".red(), e);
+                continue;
+            }
+        };
+
+        match serve_client(stream, &daemon) {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => eprintln!("{} {}", "error:".red(), e),
+        }
+    }
+
+    let _ = fs::remove_file(path);
+    daemon
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .flush()
+}
+
+// Returns whether the client asked the daemon to stop.
+#[cfg(unix)]
+fn serve_client(
+    stream: std::os::unix::net::UnixStream,
+    daemon: &std::sync::Mutex<Daemon>,
+) -> Result<bool> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::sync::PoisonError;
+
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+
+    let (response, stop) = match serde_json::from_str::<Request>(&line) {
+        Ok(request) => {
+            let stop = matches!(request, Request::Stop);
+            let mut daemon = daemon.lock().unwrap_or_else(PoisonError::into_inner);
+            (daemon.handle(request), stop)
+        }
+        Err(e) => (
+            Response {
+                error: Some(format!("Invalid request: {}", e)),
+                ..Response::default()
+            },
+            false,
+        ),
+    };
+
+    let mut reply = serde_json::to_string(&response)?;
+    reply.push('\n');
+    (&stream).write_all(reply.as_bytes())?;
+
+    Ok(stop)
+}
+
+#[cfg(not(unix))]
+fn serve(_path: &Path) -> Result<()> {
+    Err(anyhow!(
+        "trail daemon needs Unix domain sockets and is not available on this platform"
+    ))
+}
@@ -0,0 +1,158 @@
+use crate::config::Config;
+use crate::projwarp::ProjWarp;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A file whose first line names the project of the directory it is in.
+pub const MARKER_FILE: &str = ".opstrail-project";
+const MANIFESTS: [&str; 3] = ["Cargo.toml", "package.json", "go.mod"];
+
+/// One way of finding the project for a directory, tried in the order configured in
+/// `project_detectors`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectDetector {
+    /// The ProjWarp alias whose root contains the directory.
+    Projwarp,
+    /// The name of the nearest directory containing `.git`.
+    Git,
+    /// The package name in the nearest `Cargo.toml`, `package.json` or `go.mod`.
+    Manifest,
+    /// The name in the nearest `.opstrail-project` file.
+    Marker,
+}
+
+impl ProjectDetector {
+    /// Every detector, in the default order.
+    pub fn all() -> Vec<Self> {
+        vec![Self::Projwarp, Self::Git, Self::Manifest, Self::Marker]
+    }
+
+    /// The project this detector finds for `cwd`, if any.
+    pub fn detect(&self, cwd: &str) -> Option<String> {
+        let path = Path::new(cwd);
+
+        match self {
+            Self::Projwarp => ProjWarp::resolve_project(cwd),
+            Self::Git => path
+                .ancestors()
+                .find(|dir| dir.join(".git").exists())
+                .and_then(dir_name),
+            Self::Manifest => path.ancestors().find_map(manifest_name),
+            Self::Marker => path.ancestors().find_map(marker_name),
+        }
+    }
+}
+
+impl fmt::Display for ProjectDetector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Projwarp => "projwarp",
+            Self::Git => "git",
+            Self::Manifest => "manifest",
+            Self::Marker => "marker",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for ProjectDetector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "projwarp" => Ok(Self::Projwarp),
+            "git" => Ok(Self::Git),
+            "manifest" => Ok(Self::Manifest),
+            "marker" => Ok(Self::Marker),
+            other => Err(anyhow!(
+                "Unknown project detector: {}. Valid detectors: projwarp, git, manifest, marker",
+                other
+            )),
+        }
+    }
+}
+
+/// Resolves the project for `cwd` using the detectors enabled in `config`, in order.
+pub fn detect_project(config: &Config, cwd: &str) -> Option<String> {
+    config
+        .project_detectors
+        .iter()
+        .filter(|detector| {
+            **detector != ProjectDetector::Projwarp || config.enable_projwarp_integration
+        })
+        .find_map(|detector| detector.detect(cwd))
+}
+
+fn dir_name(dir: &Path) -> Option<String> {
+    dir.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+fn manifest_name(dir: &Path) -> Option<String> {
+    for manifest in MANIFESTS {
+        let path = dir.join(manifest);
+        if !path.is_file() {
+            continue;
+        }
+
+        let contents = fs::read_to_string(&path).unwrap_or_default();
+        let name = match manifest {
+            "Cargo.toml" => cargo_package_name(&contents),
+            "package.json" => serde_json::from_str::<serde_json::Value>(&contents)
+                .ok()
+                .and_then(|json| json["name"].as_str().map(str::to_string)),
+            _ => go_module_name(&contents),
+        };
+
+        return name.filter(|n| !n.is_empty()).or_else(|| dir_name(dir));
+    }
+
+    None
+}
+
+fn marker_name(dir: &Path) -> Option<String> {
+    let path = dir.join(MARKER_FILE);
+    if !path.is_file() {
+        return None;
+    }
+
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| contents.lines().next().map(|l| l.trim().to_string()))
+        .filter(|name| !name.is_empty())
+        .or_else(|| dir_name(dir))
+}
+
+fn cargo_package_name(contents: &str) -> Option<String> {
+    let mut in_package = false;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+            continue;
+        }
+
+        if in_package
+            && let Some((key, value)) = line.split_once('=')
+            && key.trim() == "name"
+        {
+            return Some(value.trim().trim_matches('"').to_string());
+        }
+    }
+
+    None
+}
+
+fn go_module_name(contents: &str) -> Option<String> {
+    contents
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("module "))
+        .and_then(|module| module.trim().trim_matches('"').rsplit('/').next())
+        .map(str::to_string)
+}
@@ -0,0 +1,497 @@
+use crate::cli::DoctorArgs;
+use crate::config::Config;
+use crate::events::Event;
+use crate::ignore::IgnoreRules;
+use crate::migrate::BadLine;
+use crate::pruner;
+use crate::redact::Redactor;
+use crate::segments::SegmentedStore;
+use crate::session::SessionManager;
+use crate::utils;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::*;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const QUARANTINE_FILE: &str = "quarantine.jsonl";
+const MAX_LISTED: usize = 10;
+
+pub struct FileCheck {
+    pub path: PathBuf,
+    pub events: Vec<Event>,
+    pub good: Vec<String>,
+    pub bad: Vec<BadLine>,
+    pub bad_lines: Vec<String>,
+    pub out_of_order: Vec<usize>,
+    pub missing_newline: bool,
+}
+
+impl FileCheck {
+    fn needs_rewrite(&self) -> bool {
+        !self.bad.is_empty() || self.missing_newline
+    }
+}
+
+#[derive(Serialize)]
+struct QuarantinedLine<'a> {
+    file: String,
+    line: usize,
+    error: &'a str,
+    content: &'a str,
+    quarantined_at: DateTime<Utc>,
+}
+
+pub fn check_file(path: &Path) -> Result<FileCheck> {
+    let contents = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut check = FileCheck {
+        path: path.to_path_buf(),
+        events: Vec::new(),
+        good: Vec::new(),
+        bad: Vec::new(),
+        bad_lines: Vec::new(),
+        out_of_order: Vec::new(),
+        missing_newline: !contents.is_empty() && contents.last() != Some(&b'\n'),
+    };
+    let mut last_seen: HashMap<String, DateTime<Utc>> = HashMap::new();
+
+    for (n, raw) in contents.split(|&b| b == b'\n').enumerate() {
+        if raw.trim_ascii().is_empty() {
+            continue;
+        }
+
+        let parsed = std::str::from_utf8(raw)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| Ok((line, serde_json::from_str::<Event>(line)?)));
+
+        match parsed {
+            Ok((line, event)) => {
+                if let Some(ref session_id) = event.session_id {
+                    let last = last_seen
+                        .entry(session_id.clone())
+                        .or_insert(event.timestamp);
+                    if event.timestamp < *last {
+                        check.out_of_order.push(n + 1);
+                    } else {
+                        *last = event.timestamp;
+                    }
+                }
+                check.good.push(line.to_string());
+                check.events.push(event);
+            }
+            Err(e) => {
+                check.bad.push(BadLine {
+                    line: n + 1,
+                    error: e.to_string(),
+                });
+                check
+                    .bad_lines
+                    .push(String::from_utf8_lossy(raw).into_owned());
+            }
+        }
+    }
+
+    Ok(check)
+}
+
+#[derive(Default)]
+struct Report {
+    errors: usize,
+    warnings: usize,
+    fixable: usize,
+}
+
+impl Report {
+    fn section(&self, title: &str) {
+        println!();
+        println!("{}", title.bold());
+    }
+
+    fn ok(&mut self, message: impl Display) {
+        println!("  {} {}", "ok".green(), message);
+    }
+
+    fn warn(&mut self, message: impl Display) {
+        self.warnings += 1;
+        println!("  {} {}", "warn".yellow(), message);
+    }
+
+    fn error(&mut self, message: impl Display) {
+        self.errors += 1;
+        println!("  {} {}", "error".red(), message);
+    }
+
+    fn fixed(&mut self, message: impl Display) {
+        println!("  {} {}", "fixed".cyan(), message);
+    }
+}
+
+pub fn doctor(args: DoctorArgs) -> Result<()> {
+    let mut report = Report::default();
+
+    println!("{}", "OpsTrail Doctor".bold().cyan());
+    println!("{}", Config::data_dir()?.display().to_string().dimmed());
+
+    let store = SegmentedStore::open()?;
+    let checks = check_history(&store, &mut report)?;
+    if args.fix {
+        fix_history(&store, &checks, &mut report)?;
+    }
+    check_manifest(&store, args.fix, &mut report)?;
+
+    check_config(&mut report)?;
+
+    let timeline_events: Vec<Event> = checks
+        .iter()
+        .filter(|check| check.path.starts_with(store.dir()))
+        .flat_map(|check| check.events.iter().cloned())
+        .collect();
+    let known: BTreeSet<String> = checks
+        .iter()
+        .flat_map(|check| &check.events)
+        .filter_map(|event| event.session_id.clone())
+        .collect();
+    check_state(&known, &timeline_events, args.fix, &mut report)?;
+
+    check_shell_integration(&mut report)?;
+
+    println!();
+    if report.errors == 0 && report.warnings == 0 {
+        println!("{}", "Everything looks healthy.".green());
+    } else {
+        println!(
+            "{} errors, {} warnings.",
+            report.errors.to_string().red(),
+            report.warnings.to_string().yellow()
+        );
+        if !args.fix && report.fixable > 0 {
+            println!(
+                "Run {} to repair what can be repaired.",
+                "trail doctor --fix".yellow()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn check_history(store: &SegmentedStore, report: &mut Report) -> Result<Vec<FileCheck>> {
+    report.section("History");
+
+    let mut paths = store.segment_paths()?;
+    if paths.is_empty() {
+        report.ok("no timeline yet");
+    }
+    paths.extend(pruner::archive_paths(&Config::data_dir()?)?);
+
+    let checks = paths
+        .iter()
+        .map(|path| check_file(path))
+        .collect::<Result<Vec<_>>>()?;
+
+    for check in &checks {
+        let name = file_name(&check.path);
+
+        if check.bad.is_empty() {
+            report.ok(format!("{}: {} events", name, check.events.len()));
+        }
+        for bad in &check.bad {
+            report.error(format!("{}:{}: {}", name, bad.line, bad.error));
+        }
+        if check.missing_newline && check.bad.is_empty() {
+            report.warn(format!("{}: last line has no trailing newline", name));
+        }
+        if check.needs_rewrite() {
+            report.fixable += 1;
+        }
+        if !check.out_of_order.is_empty() {
+            report.warn(format!(
+                "{}: {} events older than the previous event in their session (lines {})",
+                name,
+                check.out_of_order.len(),
+                line_list(&check.out_of_order)
+            ));
+        }
+    }
+
+    Ok(checks)
+}
+
+fn fix_history(store: &SegmentedStore, checks: &[FileCheck], report: &mut Report) -> Result<()> {
+    let damaged: Vec<&FileCheck> = checks.iter().filter(|c| c.needs_rewrite()).collect();
+    if damaged.is_empty() {
+        return Ok(());
+    }
+
+    let _lock = store.lock()?;
+
+    let mut fresh = Vec::new();
+    for check in &damaged {
+        let current = check_file(&check.path)?;
+        if current.needs_rewrite() {
+            fresh.push(current);
+        }
+    }
+
+    let quarantine_path = Config::data_dir()?.join(QUARANTINE_FILE);
+    let quarantined = quarantine(&quarantine_path, &fresh)?;
+
+    let contents: Vec<(&Path, String)> = fresh
+        .iter()
+        .map(|check| {
+            let mut text = String::new();
+            for line in &check.good {
+                text.push_str(line);
+                text.push('\n');
+            }
+            (check.path.as_path(), text)
+        })
+        .collect();
+    let files: Vec<(&Path, &[u8])> = contents
+        .iter()
+        .map(|(path, text)| (*path, text.as_bytes()))
+        .collect();
+    utils::write_atomic_all(&files)?;
+    for check in &fresh {
+        store.invalidate(&check.path)?;
+    }
+
+    if quarantined > 0 {
+        report.fixed(format!(
+            "moved {} bad lines to {}",
+            quarantined,
+            quarantine_path.display()
+        ));
+    }
+    for check in fresh.iter().filter(|c| c.bad.is_empty()) {
+        report.fixed(format!(
+            "{}: added trailing newline",
+            file_name(&check.path)
+        ));
+    }
+
+    Ok(())
+}
+
+fn check_manifest(store: &SegmentedStore, fix: bool, report: &mut Report) -> Result<()> {
+    report.section("Manifest");
+
+    let problems = store.check_manifest()?;
+    let stale = store.stale_indexes()?;
+    if problems.is_empty() && stale.is_empty() {
+        report.ok("manifest.json and indexes match the segments");
+        return Ok(());
+    }
+
+    for problem in &problems {
+        report.error(problem);
+    }
+    for path in &stale {
+        report.warn(format!("{}: stale index", file_name(path)));
+    }
+    report.fixable += 1;
+
+    if fix {
+        let manifest = store.rebuild_manifest()?;
+        report.fixed(format!(
+            "rebuilt manifest.json from {} segments",
+            manifest.segments.len()
+        ));
+        if !stale.is_empty() {
+            report.fixed(format!("removed {} stale indexes", stale.len()));
+        }
+    }
+
+    Ok(())
+}
+
+fn quarantine(path: &Path, checks: &[FileCheck]) -> Result<usize> {
+    let now = Utc::now();
+    let mut text = String::new();
+    let mut count = 0usize;
+
+    for check in checks {
+        for (bad, content) in check.bad.iter().zip(&check.bad_lines) {
+            let record = QuarantinedLine {
+                file: check.path.display().to_string(),
+                line: bad.line,
+                error: &bad.error,
+                content,
+                quarantined_at: now,
+            };
+            text.push_str(&serde_json::to_string(&record)?);
+            text.push('\n');
+            count += 1;
+        }
+    }
+
+    if count > 0 {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+    }
+
+    Ok(count)
+}
+
+fn check_config(report: &mut Report) -> Result<()> {
+    report.section("Config");
+
+    let path = Config::config_path()?;
+    if !path.exists() {
+        report.ok("no config.json yet, defaults in use");
+        return Ok(());
+    }
+
+    let config = match fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| Ok(serde_json::from_str::<Config>(&contents)?))
+    {
+        Ok(config) => config,
+        Err(e) => {
+            report.error(format!("config.json cannot be parsed: {}", e));
+            return Ok(());
+        }
+    };
+
+    let mut valid = true;
+    if let Err(e) = IgnoreRules::new(&config.ignore) {
+        report.error(format!("{:#}", e));
+        valid = false;
+    }
+    if let Err(e) = Redactor::new(&config) {
+        report.error(format!("{:#}", e));
+        valid = false;
+    }
+    if valid {
+        report.ok("config.json is valid");
+    }
+
+    Ok(())
+}
+
+fn check_state(
+    known: &BTreeSet<String>,
+    timeline_events: &[Event],
+    fix: bool,
+    report: &mut Report,
+) -> Result<()> {
+    report.section("Sessions");
+
+    let path = Config::state_path()?;
+    if !path.exists() {
+        report.ok("no state.json yet");
+        return Ok(());
+    }
+
+    let state = match fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| SessionManager::parse_state(&contents))
+    {
+        Ok(state) => state,
+        Err(e) => {
+            report.error(format!("state.json cannot be parsed: {}", e));
+            report.fixable += 1;
+            if fix {
+                let backup = SessionManager::rebuild(timeline_events)?;
+                report.fixed(format!(
+                    "rebuilt state.json from the timeline (old copy in {})",
+                    backup.display()
+                ));
+            }
+            return Ok(());
+        }
+    };
+
+    let orphans = state.orphans(known);
+    let dangling = state.has_dangling_current();
+
+    if orphans.is_empty() && !dangling {
+        report.ok(format!("{} open sessions", state.sessions.len()));
+        return Ok(());
+    }
+
+    for id in &orphans {
+        report.warn(format!("session {} is open but has no events", id));
+    }
+    if dangling {
+        report.warn("current session is not among the open sessions");
+    }
+    report.fixable += 1;
+
+    if fix {
+        let removed = SessionManager::repair(known)?;
+        report.fixed(format!("closed {} orphaned sessions", removed.len()));
+    }
+
+    Ok(())
+}
+
+fn check_shell_integration(report: &mut Report) -> Result<()> {
+    report.section("Shell integration");
+
+    let installed: Vec<PathBuf> = shell_profiles()
+        .into_iter()
+        .filter(|path| {
+            fs::read_to_string(path).is_ok_and(|contents| {
+                contents.contains("trail init") || contents.contains("trail log")
+            })
+        })
+        .collect();
+    let active = std::env::var("OPSTRAIL_SESSION_ID").is_ok_and(|id| !id.is_empty());
+
+    for path in &installed {
+        report.ok(format!("installed in {}", path.display()));
+    }
+
+    match (installed.is_empty(), active) {
+        (_, true) => report.ok("active in this shell"),
+        (true, false) => report.warn("no shell integration found; commands are not being recorded"),
+        (false, false) => report.warn("not active in this shell; open a new terminal"),
+    }
+
+    Ok(())
+}
+
+fn shell_profiles() -> Vec<PathBuf> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+
+    let config = dirs::config_dir().unwrap_or_else(|| home.join(".config"));
+
+    vec![
+        home.join(".bashrc"),
+        home.join(".bash_profile"),
+        home.join(".zshrc"),
+        home.join(".config/fish/config.fish"),
+        config.join("nushell/env.nu"),
+        home.join(".config/powershell/Microsoft.PowerShell_profile.ps1"),
+        home.join("Documents/PowerShell/Microsoft.PowerShell_profile.ps1"),
+        home.join("Documents/WindowsPowerShell/Microsoft.PowerShell_profile.ps1"),
+    ]
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+fn line_list(lines: &[usize]) -> String {
+    let mut list = lines
+        .iter()
+        .take(MAX_LISTED)
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    if lines.len() > MAX_LISTED {
+        list.push_str(", ...");
+    }
+    list
+}
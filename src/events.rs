@@ -1,51 +1,278 @@
+use crate::migrate;
+use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
+/// Length of [`Event::short_id`].
+pub const SHORT_ID_LEN: usize = 8;
+/// Schema version written to every new timeline record.
+pub const SCHEMA_VERSION: u32 = 2;
+/// Appended to `source` when an imported history entry had no timestamp of its own.
+pub const UNTIMED_SOURCE_SUFFIX: &str = ":untimed";
+
+/// One line of the timeline.
+///
+/// Older records are upgraded to the current schema when they are deserialized.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "serde_json::Value", into = "StoredEvent")]
 pub struct Event {
+    /// Unique id; [`Event::short_id`] is its first characters.
+    pub id: Uuid,
+    /// When the event happened.
     pub timestamp: DateTime<Utc>,
+    /// What happened.
     pub event_type: EventType,
+    /// Working directory at the time, if known.
     pub cwd: Option<String>,
+    /// Project detected for `cwd`, if any.
     pub project: Option<String>,
+    /// Shell session the event belongs to.
     pub session_id: Option<String>,
+    /// Where the event came from when it was not logged by a shell hook, such as
+    /// `import:zsh`.
+    pub source: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredEvent {
+    schema: u32,
+    id: Uuid,
+    timestamp: DateTime<Utc>,
+    event_type: EventType,
+    cwd: Option<String>,
+    project: Option<String>,
+    session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
 }
 
+impl TryFrom<serde_json::Value> for Event {
+    type Error = anyhow::Error;
+
+    fn try_from(mut value: serde_json::Value) -> Result<Self> {
+        migrate::upgrade(&mut value)?;
+        let stored: StoredEvent = serde_json::from_value(value)?;
+
+        Ok(Self {
+            id: stored.id,
+            timestamp: stored.timestamp,
+            event_type: stored.event_type,
+            cwd: stored.cwd,
+            project: stored.project,
+            session_id: stored.session_id,
+            source: stored.source,
+        })
+    }
+}
+
+impl From<Event> for StoredEvent {
+    fn from(event: Event) -> Self {
+        Self {
+            schema: SCHEMA_VERSION,
+            id: event.id,
+            timestamp: event.timestamp,
+            event_type: event.event_type,
+            cwd: event.cwd,
+            project: event.project,
+            session_id: event.session_id,
+            source: event.source,
+        }
+    }
+}
+
+/// What happened, stored as `event_type` with a snake_case `type` tag.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum EventType {
-    Command { cmd: String },
-    DirectoryChange { from: String, to: String },
+    /// A command run in the shell.
+    Command {
+        /// The command line, after redaction.
+        cmd: String,
+        /// Exit status, when the shell reported it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        exit_code: Option<i32>,
+        /// When the command started, when the shell reported it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        started_at: Option<DateTime<Utc>>,
+        /// When the command finished, when the shell reported it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        finished_at: Option<DateTime<Utc>>,
+        /// How long the command ran.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        duration_ms: Option<u64>,
+    },
+    /// The shell moved from one directory to another.
+    DirectoryChange {
+        /// The previous directory.
+        from: String,
+        /// The new directory.
+        to: String,
+    },
+    /// A shell session began.
     SessionStart,
+    /// A shell session ended.
     SessionEnd,
+    /// No activity for longer than the idle timeout.
     IdleStart,
+    /// Activity after an idle period.
     IdleEnd,
-    Note { text: String },
-    ProjectDetected { name: String },
+    /// A note added with `trail note`.
+    Note {
+        /// The note, after redaction.
+        text: String,
+    },
+    /// The shell entered a different project.
+    ProjectDetected {
+        /// The project name.
+        name: String,
+    },
+    /// Tracking was paused with `trail pause`.
+    Paused {
+        /// When the pause ends by itself, if it was given a duration.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        until: Option<DateTime<Utc>>,
+    },
+    /// Tracking was resumed with `trail unpause`.
+    Unpaused,
+}
+
+impl EventType {
+    /// The `type` tag this variant is stored under, such as `"command"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventType::Command { .. } => "command",
+            EventType::DirectoryChange { .. } => "directory_change",
+            EventType::SessionStart => "session_start",
+            EventType::SessionEnd => "session_end",
+            EventType::IdleStart => "idle_start",
+            EventType::IdleEnd => "idle_end",
+            EventType::Note { .. } => "note",
+            EventType::ProjectDetected { .. } => "project_detected",
+            EventType::Paused { .. } => "paused",
+            EventType::Unpaused => "unpaused",
+        }
+    }
+
+    /// A command with no exit code or timing information.
+    pub fn command(cmd: String) -> Self {
+        EventType::Command {
+            cmd,
+            exit_code: None,
+            started_at: None,
+            finished_at: None,
+            duration_ms: None,
+        }
+    }
 }
 
 impl Event {
+    /// Creates an event with a fresh id, stamped with the current time.
     pub fn new(event_type: EventType) -> Self {
         Self {
+            id: Uuid::new_v4(),
             timestamp: Utc::now(),
             event_type,
             cwd: None,
             project: None,
             session_id: None,
+            source: None,
         }
     }
 
+    /// Replaces the timestamp.
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Sets the working directory.
     pub fn with_cwd(mut self, cwd: String) -> Self {
         self.cwd = Some(cwd);
         self
     }
 
+    /// Sets the project.
     pub fn with_project(mut self, project: String) -> Self {
         self.project = Some(project);
         self
     }
 
+    /// Sets the session id.
     pub fn with_session(mut self, session_id: String) -> Self {
         self.session_id = Some(session_id);
         self
     }
+
+    /// Sets where the event came from.
+    pub fn with_source(mut self, source: String) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Whether `timestamp` was made up on import to keep history order, so the event
+    /// says nothing about when work happened.
+    pub fn has_synthesized_time(&self) -> bool {
+        self.source
+            .as_deref()
+            .is_some_and(|source| source.ends_with(UNTIMED_SOURCE_SUFFIX))
+    }
+
+    /// The id prefix shown by `trail timeline` and accepted by `trail forget --id`.
+    pub fn short_id(&self) -> String {
+        self.id.simple().to_string()[..SHORT_ID_LEN].to_string()
+    }
+
+    /// Whether the id starts with `prefix`, ignoring case and dashes.
+    pub fn matches_id(&self, prefix: &str) -> bool {
+        let prefix = prefix.to_lowercase().replace('-', "");
+        !prefix.is_empty() && self.id.simple().to_string().starts_with(&prefix)
+    }
+
+    /// Case-insensitive match against a command, note or project name.
+    pub fn matches_text(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        match &self.event_type {
+            EventType::Command { cmd, .. } => cmd.to_lowercase().contains(&query),
+            EventType::Note { text } => text.to_lowercase().contains(&query),
+            EventType::ProjectDetected { name } => name.to_lowercase().contains(&query),
+            _ => false,
+        }
+    }
+
+    /// The directory change and project detection events implied by moving from
+    /// `last_cwd` and `last_project` to this event's location.
+    pub fn location_changes(
+        &self,
+        last_cwd: Option<&str>,
+        last_project: Option<&str>,
+    ) -> Vec<Event> {
+        let mut changes = Vec::new();
+
+        if let (Some(from), Some(to)) = (last_cwd, self.cwd.as_deref())
+            && from != to
+        {
+            changes.push(EventType::DirectoryChange {
+                from: from.to_string(),
+                to: to.to_string(),
+            });
+        }
+
+        if let Some(name) = self.project.as_deref()
+            && last_project != Some(name)
+        {
+            changes.push(EventType::ProjectDetected {
+                name: name.to_string(),
+            });
+        }
+
+        changes
+            .into_iter()
+            .map(|event_type| Event {
+                id: Uuid::new_v4(),
+                event_type,
+                ..self.clone()
+            })
+            .collect()
+    }
 }
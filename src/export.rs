@@ -0,0 +1,275 @@
+use crate::activity;
+use crate::cli::{ExportArgs, ExportFormat};
+use crate::config::Config;
+use crate::events::{Event, EventType};
+use crate::storage::{self, EventFilter};
+use crate::utils;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use std::collections::BTreeMap;
+use std::fs;
+
+const ICS_LINE_LIMIT: usize = 75;
+const NO_PROJECT: &str = "(no project)";
+
+pub fn export(args: ExportArgs) -> Result<()> {
+    let config = Config::load()?;
+    let filter = EventFilter::from_args(&args.filter)?;
+
+    let mut events = storage::open()?.scan(&filter)?;
+    events.sort_by_key(|e| e.timestamp);
+
+    let output = match args.format {
+        ExportFormat::Csv => to_csv(&events),
+        ExportFormat::Json => to_json(&events)?,
+        ExportFormat::Markdown => to_markdown(&events, config.idle_timeout_minutes),
+        ExportFormat::Ics => to_ics(&events, config.idle_timeout_minutes, Utc::now()),
+    };
+
+    match args.output {
+        Some(path) => {
+            fs::write(&path, output).with_context(|| format!("Failed to write {}", path))?;
+            println!("Exported {} events to {}", events.len(), path);
+        }
+        None => print!("{}", output),
+    }
+
+    Ok(())
+}
+
+pub fn to_csv(events: &[Event]) -> String {
+    let mut csv = String::from(
+        "id,timestamp,type,session_id,project,cwd,detail,exit_code,duration_ms,source\n",
+    );
+
+    for event in events {
+        let (exit_code, duration_ms) = match event.event_type {
+            EventType::Command {
+                exit_code,
+                duration_ms,
+                ..
+            } => (exit_code, duration_ms),
+            _ => (None, None),
+        };
+
+        let fields = [
+            event.id.to_string(),
+            event.timestamp.to_rfc3339(),
+            event.event_type.name().to_string(),
+            event.session_id.clone().unwrap_or_default(),
+            event.project.clone().unwrap_or_default(),
+            event.cwd.clone().unwrap_or_default(),
+            detail(&event.event_type),
+            exit_code.map(|c| c.to_string()).unwrap_or_default(),
+            duration_ms.map(|d| d.to_string()).unwrap_or_default(),
+            event.source.clone().unwrap_or_default(),
+        ];
+
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+pub fn to_json(events: &[Event]) -> Result<String> {
+    let mut json = serde_json::to_string_pretty(events)?;
+    json.push('\n');
+    Ok(json)
+}
+
+pub fn to_markdown(events: &[Event], idle_timeout_minutes: u64) -> String {
+    let mut days: BTreeMap<NaiveDate, Vec<Event>> = BTreeMap::new();
+    for event in events {
+        let day = event.timestamp.with_timezone(&Local).date_naive();
+        days.entry(day).or_default().push(event.clone());
+    }
+
+    let mut md = String::from("# OpsTrail Report\n\n");
+    match (days.keys().next(), days.keys().last()) {
+        (Some(first), Some(last)) if first != last => {
+            md.push_str(&format!("{} to {}, {} events\n", first, last, events.len()))
+        }
+        (Some(day), _) => md.push_str(&format!("{}, {} events\n", day, events.len())),
+        _ => md.push_str("No activity found.\n"),
+    }
+
+    for (day, day_events) in &days {
+        let active = activity::active_time(day_events, idle_timeout_minutes);
+        md.push_str(&format!(
+            "\n## {}\n\nActive time: {}\n",
+            day.format("%A, %Y-%m-%d"),
+            utils::format_duration(active.total)
+        ));
+
+        let mut projects: BTreeMap<&str, Vec<&Event>> = BTreeMap::new();
+        for event in day_events {
+            if matches!(
+                event.event_type,
+                EventType::Command { .. } | EventType::Note { .. }
+            ) {
+                projects
+                    .entry(event.project.as_deref().unwrap_or(NO_PROJECT))
+                    .or_default()
+                    .push(event);
+            }
+        }
+
+        let mut names: Vec<&str> = projects.keys().copied().collect();
+        names.sort_by_key(|name| *name == NO_PROJECT);
+
+        for name in names {
+            let time = active
+                .by_project
+                .get(name)
+                .map(|d| format!(" ({})", utils::format_duration(*d)))
+                .unwrap_or_default();
+            md.push_str(&format!("\n### {}{}\n\n", name, time));
+
+            for event in &projects[name] {
+                let at = event.timestamp.with_timezone(&Local).format("%H:%M");
+                let line = match &event.event_type {
+                    EventType::Command {
+                        cmd,
+                        exit_code,
+                        duration_ms,
+                        ..
+                    } => {
+                        let mut outcome = Vec::new();
+                        if let Some(code) = exit_code.filter(|&c| c != 0) {
+                            outcome.push(format!("exit {}", code));
+                        }
+                        if let Some(ms) = duration_ms.filter(|&ms| ms >= 1000) {
+                            outcome.push(utils::format_duration(Duration::milliseconds(ms as i64)));
+                        }
+                        let outcome = if outcome.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" ({})", outcome.join(", "))
+                        };
+                        format!("{}{}", inline_code(cmd), outcome)
+                    }
+                    EventType::Note { text } => format!("**Note:** {}", text.replace('\n', " ")),
+                    _ => continue,
+                };
+                md.push_str(&format!("- {} {}\n", at, line));
+            }
+        }
+    }
+
+    md
+}
+
+pub fn to_ics(events: &[Event], idle_timeout_minutes: u64, now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//OpsTrail//trail export//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+
+    for block in activity::work_blocks(events, idle_timeout_minutes) {
+        let end = block.end.max(block.start + Duration::minutes(1));
+        let summary = if block.projects.is_empty() {
+            "Terminal session".to_string()
+        } else {
+            block
+                .projects
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut description = format!("{} commands", block.commands);
+        if block.notes > 0 {
+            description.push_str(&format!(", {} notes", block.notes));
+        }
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!(
+            "UID:{}-{}@opstrail",
+            block.session_id.as_deref().unwrap_or("imported"),
+            block.start.timestamp()
+        ));
+        lines.push(format!("DTSTAMP:{}", ics_time(now)));
+        lines.push(format!("DTSTART:{}", ics_time(block.start)));
+        lines.push(format!("DTEND:{}", ics_time(end)));
+        lines.push(format!("SUMMARY:{}", ics_text(&summary)));
+        lines.push(format!("DESCRIPTION:{}", ics_text(&description)));
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics = String::new();
+    for line in lines {
+        ics.push_str(&fold_ics_line(&line));
+        ics.push_str("\r\n");
+    }
+    ics
+}
+
+fn detail(event_type: &EventType) -> String {
+    match event_type {
+        EventType::Command { cmd, .. } => cmd.clone(),
+        EventType::Note { text } => text.clone(),
+        EventType::ProjectDetected { name } => name.clone(),
+        EventType::DirectoryChange { to, .. } => to.clone(),
+        EventType::Paused { until: Some(until) } => until.to_rfc3339(),
+        _ => String::new(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn inline_code(text: &str) -> String {
+    let text = text.replace('\n', " ");
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+
+    let fence = "`".repeat(longest + 1);
+    if longest == 0 {
+        format!("{}{}{}", fence, text, fence)
+    } else {
+        format!("{} {} {}", fence, text, fence)
+    }
+}
+
+fn ics_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn fold_ics_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > ICS_LINE_LIMIT {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += len;
+    }
+
+    folded
+}
@@ -0,0 +1,220 @@
+use crate::cli::ForgetArgs;
+use crate::config::Config;
+use crate::events::{Event, EventType};
+use crate::pruner;
+use crate::segments::SegmentedStore;
+use crate::storage::EventFilter;
+use crate::utils;
+use anyhow::{Context, Result, anyhow};
+use chrono::Local;
+use colored::*;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+const PREVIEW_LIMIT: usize = 20;
+
+pub struct ForgetCriteria {
+    pub filter: EventFilter,
+    pub query: Option<String>,
+    pub ids: Vec<String>,
+}
+
+impl ForgetCriteria {
+    pub fn from_args(args: &ForgetArgs) -> Result<Self> {
+        let filter = EventFilter::from_args(&args.filter)?;
+        let query = args.query.clone().filter(|q| !q.is_empty());
+        let ids: Vec<String> = args
+            .id
+            .iter()
+            .filter(|id| !id.is_empty())
+            .cloned()
+            .collect();
+
+        if query.is_none() && ids.is_empty() && filter.is_unbounded() {
+            return Err(anyhow!(
+                "Refusing to forget everything. Give a search term, --id, --project, --session, --date, --today, --from or --to"
+            ));
+        }
+
+        Ok(Self { filter, query, ids })
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        self.filter.matches(event)
+            && self
+                .query
+                .as_deref()
+                .is_none_or(|query| event.matches_text(query))
+            && (self.ids.is_empty() || self.ids.iter().any(|id| event.matches_id(id)))
+    }
+}
+
+pub struct Sweep {
+    pub path: PathBuf,
+    pub kept: Vec<String>,
+    pub forgotten: Vec<Event>,
+}
+
+pub fn sweep_file(path: &Path, criteria: &ForgetCriteria) -> Result<Sweep> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut kept = Vec::new();
+    let mut forgotten = Vec::new();
+
+    for line in contents.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<Event>(line) {
+            Ok(event) if criteria.matches(&event) => forgotten.push(event),
+            _ => kept.push(line.to_string()),
+        }
+    }
+
+    Ok(Sweep {
+        path: path.to_path_buf(),
+        kept,
+        forgotten,
+    })
+}
+
+pub fn forget(args: ForgetArgs) -> Result<()> {
+    let criteria = ForgetCriteria::from_args(&args)?;
+    let store = SegmentedStore::open()?;
+
+    let preview = sweep_all(&store, &criteria)?;
+    let total: usize = preview.iter().map(|s| s.forgotten.len()).sum();
+
+    if total == 0 {
+        println!("No matching events found.");
+        return Ok(());
+    }
+
+    print_preview(&preview, total);
+
+    if args.dry_run {
+        println!(
+            "{} {} events would be forgotten.",
+            "[dry-run]".yellow(),
+            total.to_string().yellow()
+        );
+        return Ok(());
+    }
+
+    if !args.yes && !confirm(total)? {
+        println!("Nothing was deleted.");
+        return Ok(());
+    }
+
+    let _lock = store.lock()?;
+
+    let sweeps: Vec<Sweep> = sweep_all(&store, &criteria)?
+        .into_iter()
+        .filter(|s| !s.forgotten.is_empty())
+        .collect();
+
+    let contents: Vec<(PathBuf, String)> = sweeps
+        .iter()
+        .map(|sweep| {
+            let mut text = String::new();
+            for line in &sweep.kept {
+                text.push_str(line);
+                text.push('\n');
+            }
+            (sweep.path.clone(), text)
+        })
+        .collect();
+    let files: Vec<(&Path, &[u8])> = contents
+        .iter()
+        .map(|(path, text)| (path.as_path(), text.as_bytes()))
+        .collect();
+    utils::write_atomic_all(&files)?;
+    for sweep in &sweeps {
+        store.invalidate(&sweep.path)?;
+    }
+
+    let forgotten: usize = sweeps.iter().map(|s| s.forgotten.len()).sum();
+    println!(
+        "Forgot {} events from {} files.",
+        forgotten.to_string().yellow(),
+        sweeps.len()
+    );
+
+    Ok(())
+}
+
+fn sweep_all(store: &SegmentedStore, criteria: &ForgetCriteria) -> Result<Vec<Sweep>> {
+    let mut paths = store.segment_paths()?;
+    paths.extend(pruner::archive_paths(&Config::data_dir()?)?);
+
+    paths
+        .iter()
+        .map(|path| sweep_file(path, criteria))
+        .collect()
+}
+
+fn print_preview(sweeps: &[Sweep], total: usize) {
+    println!("{}", "Events to forget:".bold().cyan());
+    println!();
+
+    let mut shown = 0;
+    for sweep in sweeps.iter().filter(|s| !s.forgotten.is_empty()) {
+        println!(
+            "  {} ({})",
+            sweep.path.display().to_string().dimmed(),
+            sweep.forgotten.len()
+        );
+        for event in &sweep.forgotten {
+            if shown == PREVIEW_LIMIT {
+                break;
+            }
+            println!("    {}", describe(event));
+            shown += 1;
+        }
+    }
+
+    if total > shown {
+        println!("    {}", format!("... and {} more", total - shown).dimmed());
+    }
+    println!();
+}
+
+fn describe(event: &Event) -> String {
+    let time = event
+        .timestamp
+        .with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    let project = event
+        .project
+        .as_ref()
+        .map(|p| format!(" [{}]", p.cyan()))
+        .unwrap_or_default();
+    let what = match &event.event_type {
+        EventType::Command { cmd, .. } => cmd.yellow().to_string(),
+        EventType::Note { text } => format!("note: {}", text.green()),
+        EventType::ProjectDetected { name } => format!("project {}", name.cyan()),
+        EventType::DirectoryChange { to, .. } => format!("cd {}", to.blue()),
+        other => format!("{:?}", other).dimmed().to_string(),
+    };
+
+    format!(
+        "{} {}{} {}",
+        time.dimmed(),
+        event.short_id().dimmed(),
+        project,
+        what
+    )
+}
+
+fn confirm(total: usize) -> Result<bool> {
+    print!("Forget {} events? [y/N] ", total);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
@@ -0,0 +1,99 @@
+use crate::config::IgnoreConfig;
+use anyhow::{Context, Result};
+use regex::Regex;
+
+const REGEX_PREFIX: &str = "re:";
+
+pub struct IgnoreRules {
+    leading_space: bool,
+    commands: Vec<Regex>,
+    directories: Vec<Regex>,
+}
+
+impl IgnoreRules {
+    pub fn new(config: &IgnoreConfig) -> Result<Self> {
+        Ok(Self {
+            leading_space: config.leading_space,
+            commands: compile(&config.commands, false)?,
+            directories: compile(&config.directories, true)?,
+        })
+    }
+
+    pub fn ignores_command(&self, cmd: &str) -> bool {
+        if self.leading_space && cmd.starts_with(' ') {
+            return true;
+        }
+
+        let cmd = cmd.trim();
+        let program = cmd.split_whitespace().next().unwrap_or_default();
+
+        self.commands
+            .iter()
+            .any(|rule| rule.is_match(cmd) || rule.is_match(program))
+    }
+
+    pub fn ignores_directory(&self, cwd: &str) -> bool {
+        let cwd = normalize_dir(cwd);
+        self.directories.iter().any(|rule| rule.is_match(&cwd))
+    }
+}
+
+fn compile(patterns: &[String], directory: bool) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            let source = match pattern.strip_prefix(REGEX_PREFIX) {
+                Some(regex) => regex.to_string(),
+                None if directory => glob_to_regex(&normalize_dir(&expand_home(pattern)), true),
+                None => glob_to_regex(pattern.trim(), false),
+            };
+            Regex::new(&source).with_context(|| format!("Invalid ignore rule: {}", pattern))
+        })
+        .collect()
+}
+
+fn glob_to_regex(glob: &str, directory: bool) -> String {
+    let any = if directory { "[^/]" } else { "(?s:.)" };
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if regex.ends_with('/') && chars.peek().is_none() {
+                    regex.pop();
+                    regex.push_str("(/.*)?");
+                } else {
+                    regex.push_str("(?s:.*)");
+                }
+            }
+            '*' => {
+                regex.push_str(any);
+                regex.push('*');
+            }
+            '?' => regex.push_str(any),
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    regex.push('$');
+    regex
+}
+
+fn expand_home(pattern: &str) -> String {
+    match (pattern.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{}{}", home.to_string_lossy(), rest)
+        }
+        _ => pattern.to_string(),
+    }
+}
+
+fn normalize_dir(path: &str) -> String {
+    let mut normalized = path.replace('\\', "/");
+    while normalized.len() > 1 && normalized.ends_with('/') {
+        normalized.pop();
+    }
+    normalized
+}
@@ -0,0 +1,441 @@
+use crate::cli::{HistoryFormat, ImportArgs};
+use crate::config::Config;
+use crate::events::{Event, EventType, UNTIMED_SOURCE_SUFFIX};
+use crate::ignore::IgnoreRules;
+use crate::pruner;
+use crate::redact::Redactor;
+use crate::segments::{self, SegmentedStore};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Duration, Utc};
+use colored::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+const IMPORT_ID_NAMESPACE: Uuid = Uuid::from_u128(0x6f70_7374_7261_696c_8000_0000_0000_0002);
+
+// zsh escapes special bytes in its history file as 0x83 followed by the byte xor 32.
+const ZSH_META: u8 = 0x83;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub cmd: String,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub elapsed_secs: Option<i64>,
+}
+
+impl HistoryEntry {
+    fn new(cmd: String, timestamp: Option<DateTime<Utc>>) -> Self {
+        Self {
+            cmd,
+            timestamp,
+            elapsed_secs: None,
+        }
+    }
+}
+
+pub fn parse(format: HistoryFormat, contents: &[u8]) -> Vec<HistoryEntry> {
+    match format {
+        HistoryFormat::Bash => parse_bash(&String::from_utf8_lossy(contents)),
+        HistoryFormat::Zsh => parse_zsh(contents),
+        HistoryFormat::Fish => parse_fish(&String::from_utf8_lossy(contents)),
+        HistoryFormat::Powershell => parse_powershell(&String::from_utf8_lossy(contents)),
+    }
+}
+
+pub fn parse_bash(text: &str) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut timestamp = None;
+
+    for line in text.lines() {
+        if let Some(seconds) = line.strip_prefix('#')
+            && !seconds.is_empty()
+            && seconds.bytes().all(|b| b.is_ascii_digit())
+        {
+            timestamp = seconds.parse().ok().and_then(from_unix);
+            continue;
+        }
+
+        if !line.trim().is_empty() {
+            entries.push(HistoryEntry::new(line.to_string(), timestamp.take()));
+        }
+    }
+
+    entries
+}
+
+pub fn parse_zsh(contents: &[u8]) -> Vec<HistoryEntry> {
+    let text = String::from_utf8_lossy(&unmetafy(contents)).into_owned();
+    let mut entries = Vec::new();
+    let mut lines = text.lines();
+
+    while let Some(first) = lines.next() {
+        let mut cmd = first.to_string();
+        while cmd.ends_with('\\') {
+            cmd.pop();
+            cmd.push('\n');
+            match lines.next() {
+                Some(next) => cmd.push_str(next),
+                None => break,
+            }
+        }
+
+        let mut entry = HistoryEntry::new(cmd, None);
+        if let Some(rest) = entry.cmd.strip_prefix(": ")
+            && let Some((meta, command)) = rest.split_once(';')
+            && let Some((start, elapsed)) = meta.split_once(':')
+            && let (Ok(start), Ok(elapsed)) = (start.trim().parse(), elapsed.trim().parse())
+        {
+            entry = HistoryEntry {
+                cmd: command.to_string(),
+                timestamp: from_unix(start),
+                elapsed_secs: Some(elapsed),
+            };
+        }
+
+        if !entry.cmd.trim().is_empty() {
+            entries.push(entry);
+        }
+    }
+
+    entries
+}
+
+pub fn parse_fish(text: &str) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+
+    for line in text.lines() {
+        if let Some(cmd) = line.strip_prefix("- cmd: ") {
+            entries.push(HistoryEntry::new(unescape_fish(cmd), None));
+        } else if let Some(when) = line.trim_start().strip_prefix("when: ")
+            && let Some(entry) = entries.last_mut()
+        {
+            entry.timestamp = when.trim().parse().ok().and_then(from_unix);
+        }
+    }
+
+    entries.retain(|entry| !entry.cmd.trim().is_empty());
+    entries
+}
+
+pub fn parse_powershell(text: &str) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut lines = text.lines();
+
+    while let Some(first) = lines.next() {
+        let mut cmd = first.to_string();
+        while cmd.ends_with('`') {
+            cmd.pop();
+            cmd.push('\n');
+            match lines.next() {
+                Some(next) => cmd.push_str(next),
+                None => break,
+            }
+        }
+
+        if !cmd.trim().is_empty() {
+            entries.push(HistoryEntry::new(cmd, None));
+        }
+    }
+
+    entries
+}
+
+/// Converts parsed history to events with ids derived from the entries, so importing the
+/// same history twice yields the same ids. Untimed entries are numbered from
+/// `untimed_offset`, their position in the history imported so far; see [`untimed_overlap`].
+pub fn to_events(
+    format: HistoryFormat,
+    entries: &[HistoryEntry],
+    anchor: DateTime<Utc>,
+    untimed_offset: usize,
+) -> Vec<Event> {
+    let source = format!("import:{}", format);
+    let leading = entries
+        .iter()
+        .take_while(|entry| entry.timestamp.is_none())
+        .count();
+    let first_known = entries
+        .get(leading)
+        .and_then(|entry| entry.timestamp)
+        .unwrap_or(anchor);
+
+    let mut seen: HashMap<(String, &str), usize> = HashMap::new();
+    let mut previous: Option<DateTime<Utc>> = None;
+    let mut untimed = untimed_offset;
+    let mut events = Vec::with_capacity(entries.len());
+
+    for (i, entry) in entries.iter().enumerate() {
+        let started = match (entry.timestamp, previous) {
+            (Some(timestamp), _) => timestamp,
+            (None, Some(previous)) => previous + Duration::seconds(1),
+            (None, None) => first_known - Duration::seconds((leading - i) as i64),
+        };
+        previous = Some(started);
+
+        let key = match entry.timestamp {
+            Some(timestamp) => timestamp.timestamp().to_string(),
+            None => {
+                untimed += 1;
+                format!("untimed:{}", untimed - 1)
+            }
+        };
+        let occurrence = seen.entry((key.clone(), &entry.cmd)).or_default();
+        *occurrence += 1;
+        let id = Uuid::new_v5(
+            &IMPORT_ID_NAMESPACE,
+            format!("{}\0{}\0{}\0{}", format, key, entry.cmd, occurrence).as_bytes(),
+        );
+
+        let event_type = match entry.elapsed_secs {
+            Some(elapsed) => EventType::Command {
+                cmd: entry.cmd.clone(),
+                exit_code: None,
+                started_at: Some(started),
+                finished_at: Some(started + Duration::seconds(elapsed)),
+                duration_ms: Some(elapsed.max(0) as u64 * 1000),
+            },
+            None => EventType::command(entry.cmd.clone()),
+        };
+        let finished = started + Duration::seconds(entry.elapsed_secs.unwrap_or(0));
+
+        let source = match entry.timestamp {
+            Some(_) => source.clone(),
+            None => format!("{}{}", source, UNTIMED_SOURCE_SUFFIX),
+        };
+        let mut event = Event::new(event_type)
+            .with_timestamp(finished)
+            .with_source(source);
+        event.id = id;
+        events.push(event);
+    }
+
+    events
+}
+
+/// How many commands at the start of `next` repeat the end of `previous`. A shell that
+/// trims its history drops lines from the front and appends new ones, so the untimed
+/// commands already imported end with the ones the file now starts with.
+pub fn untimed_overlap(previous: &[String], next: &[String]) -> usize {
+    let tail = &previous[previous.len() - previous.len().min(next.len())..];
+
+    // Prefix function over `next`, a separator, then `tail`: its last value is the
+    // longest prefix of `next` that is also a suffix of `tail`.
+    let sequence: Vec<Option<&str>> = next
+        .iter()
+        .map(|cmd| Some(cmd.as_str()))
+        .chain([None])
+        .chain(tail.iter().map(|cmd| Some(cmd.as_str())))
+        .collect();
+    let mut prefix = vec![0usize; sequence.len()];
+    for i in 1..sequence.len() {
+        let mut k = prefix[i - 1];
+        while k > 0 && sequence[i] != sequence[k] {
+            k = prefix[k - 1];
+        }
+        if sequence[i].is_some() && sequence[i] == sequence[k] {
+            k += 1;
+        }
+        prefix[i] = k;
+    }
+
+    prefix.last().copied().unwrap_or(0)
+}
+
+pub fn merge_lines(existing: &str, mut imported: Vec<Event>) -> Result<Vec<String>> {
+    imported.sort_by_key(|event| event.timestamp);
+    let mut imported = imported.into_iter().peekable();
+    let mut lines = Vec::new();
+
+    for line in existing.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Ok(event) = serde_json::from_str::<Event>(line) {
+            while let Some(next) = imported.next_if(|next| next.timestamp < event.timestamp) {
+                lines.push(serde_json::to_string(&next)?);
+            }
+        }
+        lines.push(line.to_string());
+    }
+
+    for event in imported {
+        lines.push(serde_json::to_string(&event)?);
+    }
+
+    Ok(lines)
+}
+
+pub fn import_history(args: ImportArgs) -> Result<()> {
+    let path = match args.path {
+        Some(path) => PathBuf::from(path),
+        None => default_history_path(args.format)?,
+    };
+    let contents = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let anchor = fs::metadata(&path)
+        .and_then(|meta| meta.modified())
+        .map(DateTime::<Utc>::from)
+        .unwrap_or_else(|_| Utc::now());
+
+    let config = Config::load()?;
+    let rules = IgnoreRules::new(&config.ignore)?;
+    let redactor = Redactor::new(&config)?;
+
+    let mut entries = parse(args.format, &contents);
+    let parsed = entries.len();
+    entries.retain(|entry| !rules.ignores_command(&entry.cmd));
+    let ignored = parsed - entries.len();
+
+    let store = SegmentedStore::open()?;
+    let _lock = store.lock()?;
+
+    let untimed_source = format!("import:{}{}", args.format, UNTIMED_SOURCE_SUFFIX);
+    let mut known = HashSet::new();
+    let mut previous: Vec<(DateTime<Utc>, String)> = Vec::new();
+    let mut files = store.segment_paths()?;
+    files.extend(pruner::archive_paths(&Config::data_dir()?)?);
+    for file in files {
+        for event in fs::read_to_string(&file)?
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Event>(line).ok())
+        {
+            if let EventType::Command { ref cmd, .. } = event.event_type
+                && event.source.as_deref() == Some(untimed_source.as_str())
+            {
+                previous.push((event.timestamp, cmd.clone()));
+            }
+            known.insert(event.id);
+        }
+    }
+    previous.sort_by_key(|(timestamp, _)| *timestamp);
+    let previous: Vec<String> = previous.into_iter().map(|(_, cmd)| cmd).collect();
+
+    // Untimed commands have no stable key of their own, so line them up with the ones
+    // imported before and number the new ones after them.
+    let next: Vec<String> = entries
+        .iter()
+        .filter(|entry| entry.timestamp.is_none())
+        .map(|entry| redactor.redact(&entry.cmd))
+        .collect();
+    let offset = previous.len() - untimed_overlap(&previous, &next);
+
+    let mut events = to_events(args.format, &entries, anchor, offset);
+    for event in &mut events {
+        redactor.redact_event(event);
+    }
+    events.retain(|event| !known.contains(&event.id));
+    let duplicates = parsed - ignored - events.len();
+
+    if events.is_empty() {
+        println!(
+            "Nothing new to import from {} ({} already imported).",
+            path.display(),
+            duplicates
+        );
+        return Ok(());
+    }
+
+    if args.dry_run {
+        println!(
+            "{} {} commands would be imported from {} ({} already imported, {} ignored).",
+            "[dry-run]".yellow(),
+            events.len().to_string().yellow(),
+            path.display(),
+            duplicates,
+            ignored
+        );
+        return Ok(());
+    }
+
+    let imported = events.len();
+    let mut by_month: BTreeMap<String, Vec<Event>> = BTreeMap::new();
+    for event in events {
+        by_month
+            .entry(segments::month(event.timestamp))
+            .or_default()
+            .push(event);
+    }
+    for (month, events) in by_month {
+        let path = store.segment_path(&month);
+        let existing = match fs::read_to_string(&path) {
+            Ok(existing) => existing,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e).context("Failed to read the timeline"),
+        };
+        store.replace_lines(&path, &merge_lines(&existing, events)?)?;
+    }
+
+    println!(
+        "Imported {} commands from {} ({} already imported, {} ignored).",
+        imported.to_string().green(),
+        path.display(),
+        duplicates,
+        ignored
+    );
+
+    Ok(())
+}
+
+fn default_history_path(format: HistoryFormat) -> Result<PathBuf> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
+
+    Ok(match format {
+        HistoryFormat::Bash => home.join(".bash_history"),
+        HistoryFormat::Zsh => home.join(".zsh_history"),
+        HistoryFormat::Fish => home.join(".local/share/fish/fish_history"),
+        HistoryFormat::Powershell => {
+            let data = dirs::data_dir().unwrap_or_else(|| home.join(".local/share"));
+            if cfg!(windows) {
+                data.join("Microsoft/Windows/PowerShell/PSReadLine/ConsoleHost_history.txt")
+            } else {
+                data.join("powershell/PSReadLine/ConsoleHost_history.txt")
+            }
+        }
+    })
+}
+
+fn from_unix(seconds: i64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(seconds, 0)
+}
+
+fn unmetafy(contents: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(contents.len());
+    let mut iter = contents.iter();
+
+    while let Some(&b) = iter.next() {
+        if b == ZSH_META {
+            if let Some(&next) = iter.next() {
+                bytes.push(next ^ 32);
+            }
+        } else {
+            bytes.push(b);
+        }
+    }
+
+    bytes
+}
+
+fn unescape_fish(cmd: &str) -> String {
+    let mut unescaped = String::with_capacity(cmd.len());
+    let mut chars = cmd.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
@@ -0,0 +1,54 @@
+use crate::cli::{InitArgs, Shell};
+use anyhow::Result;
+
+const BIN_PLACEHOLDER: &str = "__OPSTRAIL_BIN__";
+
+const POSIX: &str = include_str!("../assets/init/posix.sh");
+const BASH: &str = include_str!("../assets/init/bash.sh");
+const ZSH: &str = include_str!("../assets/init/zsh.sh");
+const FISH: &str = include_str!("../assets/init/fish.fish");
+const POWERSHELL: &str = include_str!("../assets/init/powershell.ps1");
+const NUSHELL: &str = include_str!("../assets/init/nushell.nu");
+
+pub fn init(args: InitArgs) -> Result<()> {
+    let trail = std::env::current_exe()
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "trail".to_string());
+
+    print!("{}", script(args.shell, &trail));
+    Ok(())
+}
+
+pub fn script(shell: Shell, trail: &str) -> String {
+    let header = format!(
+        "# OpsTrail shell integration for {}, generated by trail {}.\n",
+        shell,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let (body, trail) = match shell {
+        Shell::Bash => (format!("{}\n{}", POSIX, BASH), posix_quote(trail)),
+        Shell::Zsh => (format!("{}\n{}", POSIX, ZSH), posix_quote(trail)),
+        Shell::Fish => (FISH.to_string(), fish_quote(trail)),
+        Shell::Powershell => (POWERSHELL.to_string(), powershell_quote(trail)),
+        Shell::Nushell => (NUSHELL.to_string(), nushell_quote(trail)),
+    };
+
+    format!("{}{}", header, body.replace(BIN_PLACEHOLDER, &trail))
+}
+
+fn posix_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn powershell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn nushell_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
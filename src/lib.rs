@@ -0,0 +1,116 @@
+//! OpsTrail records terminal activity to a JSONL timeline in `~/.opstrail`.
+//! The `trail` binary is a thin CLI over this crate, and other tools can use
+//! the same types to read, filter and append events.
+//!
+//! The main entry points are:
+//!
+//! - [`open_timeline`] opens the timeline as an [`EventStore`]. It is a
+//!   [`SegmentedStore`] with one [`IndexedStore`] file per month, and
+//!   [`IndexedStore::new`] opens a single file at any path.
+//! - [`EventFilter`] selects events by time range, project or session.
+//! - [`Event`] and [`EventType`] are the records in the timeline.
+//! - [`Config`] holds the settings in `~/.opstrail/config.json`.
+//! - [`detect_project`] and [`ProjWarp::resolve_project`] resolve the project
+//!   for a directory.
+//! - [`compute_stats`] computes the report printed by `trail stats`.
+//!
+//! ```no_run
+//! use chrono::{Duration, Local};
+//! use opstrail::{Config, Event, EventFilter, EventType, compute_stats, detect_project, open_timeline};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let config = Config::load()?;
+//! let store = open_timeline()?;
+//!
+//! let to = Local::now().date_naive();
+//! let from = to - Duration::days(7);
+//! let events = store.scan(&EventFilter::dates(from, to).with_project("api".to_string()))?;
+//!
+//! let stats = compute_stats(&events, from, to, config.idle_timeout_minutes);
+//! println!("{} commands, {}s active", stats.commands, stats.active_secs);
+//!
+//! let cwd = "/home/me/src/api";
+//! let mut note = Event::new(EventType::Note {
+//!     text: "deployed v2".to_string(),
+//! })
+//! .with_cwd(cwd.to_string());
+//! if let Some(project) = detect_project(&config, cwd) {
+//!     note = note.with_project(project);
+//! }
+//! store.append(&note)?;
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+
+mod activity;
+mod cli;
+mod config;
+mod daemon;
+mod detect;
+mod doctor;
+mod events;
+mod export;
+mod forget;
+mod ignore;
+mod import;
+mod init;
+mod lock;
+mod logger;
+mod migrate;
+mod projwarp;
+mod pruner;
+mod query;
+mod redact;
+mod segments;
+mod session;
+mod storage;
+mod utils;
+
+#[cfg(test)]
+mod tests;
+
+pub use config::{AutoCdConfig, Config, IgnoreConfig, RedactionConfig};
+pub use detect::{ProjectDetector, detect_project};
+pub use events::{Event, EventType};
+pub use projwarp::{ProjWarp, ProjWarpConfig, ProjectResolver};
+pub use query::{CommandCount, DayTime, ProjectTime, SlowCommand, Stats, compute_stats};
+pub use segments::{Manifest, Segment, SegmentedStore};
+pub use storage::{EventFilter, EventStore, IndexedStore, Summary, open as open_timeline};
+
+use anyhow::Result;
+use clap::Parser;
+use cli::{Cli, Command};
+
+/// Runs `trail` with the arguments of the current process. This is all the binary does.
+pub fn run() -> Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Log(args) => logger::log_event(args)?,
+        Command::Back(args) => query::time_travel(args)?,
+        Command::Search(args) => query::search(args)?,
+        Command::Stats(args) => query::stats(args)?,
+        Command::Timeline(args) => query::timeline(args)?,
+        Command::Note(args) => logger::add_note(args)?,
+        Command::Resume(args) => query::resume(args)?,
+        Command::Today(args) => query::today(args)?,
+        Command::Sessions(args) => session::list_sessions(args)?,
+        Command::Projects(args) => query::projects(args)?,
+        Command::Config(args) => config::handle_config_command(args)?,
+        Command::Prune(args) => pruner::prune(args)?,
+        Command::Redact(args) => redact::redact_history(args)?,
+        Command::Pause(args) => session::pause_tracking(args)?,
+        Command::Unpause(args) => session::unpause_tracking(args)?,
+        Command::Forget(args) => forget::forget(args)?,
+        Command::Migrate(args) => migrate::migrate(args)?,
+        Command::Doctor(args) => doctor::doctor(args)?,
+        Command::Import(args) => import::import_history(args)?,
+        Command::Export(args) => export::export(args)?,
+        Command::Init(args) => init::init(args)?,
+        Command::Daemon(args) => daemon::daemon(args)?,
+    }
+
+    Ok(())
+}
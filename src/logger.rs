@@ -1,76 +1,281 @@
+use crate::activity;
 use crate::cli::{LogArgs, NoteArgs};
 use crate::config::Config;
+use crate::daemon;
+use crate::detect;
 use crate::events::{Event, EventType};
-use crate::projwarp::ProjWarp;
-use crate::session::SessionManager;
+use crate::ignore::IgnoreRules;
+use crate::redact::Redactor;
+use crate::session::{SessionChange, SessionManager, SessionState};
+use crate::storage;
+use crate::utils;
 use anyhow::Result;
-use std::fs::OpenOptions;
-use std::io::Write;
+use chrono::{DateTime, Utc};
 
-pub fn log_event(args: LogArgs) -> Result<()> {
-    let config = Config::load()?;
-    let timeline_path = Config::timeline_path()?;
-
-    let event_type = if args.session_start {
-        SessionManager::new_session()?;
-        EventType::SessionStart
-    } else if args.session_end {
-        EventType::SessionEnd
-    } else if args.idle_start {
-        EventType::IdleStart
-    } else if args.idle_end {
-        EventType::IdleEnd
-    } else if let Some(cmd) = args.cmd {
-        EventType::Command { cmd }
-    } else {
-        return Ok(());
-    };
+/// A `trail log` call after ignore rules, redaction and project detection, waiting for
+/// its session.
+pub struct Pending {
+    event: Event,
+    session: Option<String>,
+    session_start: bool,
+    resumed_at: DateTime<Utc>,
+    explicit_idle: bool,
+}
 
-    let mut event = Event::new(event_type);
+/// The events to append and the session changes to save for one `trail log` call.
+pub struct Logged {
+    pub session_id: String,
+    pub events: Vec<Event>,
+    pub changes: Vec<SessionChange>,
+}
 
-    if let Some(cwd) = args.cwd.or_else(|| {
-        std::env::current_dir()
-            .ok()
-            .map(|p| p.to_string_lossy().to_string())
-    }) {
-        let project = if config.enable_projwarp_integration {
-            args.project.or_else(|| ProjWarp::resolve_project(&cwd))
+/// Turns `trail log` arguments into timeline events. Shared by direct logging and the daemon.
+pub struct Recorder {
+    config: Config,
+    rules: IgnoreRules,
+    redactor: Redactor,
+}
+
+impl Recorder {
+    pub fn new(config: Config) -> Result<Self> {
+        let rules = IgnoreRules::new(&config.ignore)?;
+        let redactor = Redactor::new(&config)?;
+        Ok(Self {
+            config,
+            rules,
+            redactor,
+        })
+    }
+
+    /// Returns `None` when there is nothing to record or the command is ignored.
+    pub fn prepare(&self, args: LogArgs) -> Result<Option<Pending>> {
+        let event_type = if args.session_start {
+            EventType::SessionStart
+        } else if args.session_end {
+            EventType::SessionEnd
+        } else if args.idle_start {
+            EventType::IdleStart
+        } else if args.idle_end {
+            EventType::IdleEnd
+        } else if let Some(cmd) = args.cmd {
+            let started_at = args
+                .started_at
+                .as_deref()
+                .map(utils::parse_timestamp)
+                .transpose()?;
+            let finished_at = args
+                .finished_at
+                .as_deref()
+                .map(utils::parse_timestamp)
+                .transpose()?;
+            let duration_ms = match (started_at, finished_at) {
+                (Some(start), Some(end)) if end >= start => {
+                    Some((end - start).num_milliseconds() as u64)
+                }
+                _ => args.duration_ms,
+            };
+
+            EventType::Command {
+                cmd,
+                exit_code: args.exit_code,
+                started_at,
+                finished_at,
+                duration_ms,
+            }
         } else {
-            args.project
+            return Ok(None);
         };
 
-        event = event.with_cwd(cwd);
-        if let Some(proj) = project {
-            event = event.with_project(proj);
+        let resumed_at = match event_type {
+            EventType::Command {
+                started_at: Some(started_at),
+                ..
+            } => started_at,
+            _ => Utc::now(),
+        };
+        let explicit_idle = matches!(event_type, EventType::IdleStart | EventType::IdleEnd);
+
+        let ignored_dir = args
+            .cwd
+            .as_deref()
+            .is_some_and(|cwd| self.rules.ignores_directory(cwd));
+
+        if let EventType::Command { ref cmd, .. } = event_type
+            && (ignored_dir || self.rules.ignores_command(cmd))
+        {
+            return Ok(None);
+        }
+
+        let mut event = Event::new(event_type);
+        self.redactor.redact_event(&mut event);
+
+        if let Some(cwd) = args.cwd.filter(|_| !ignored_dir) {
+            let project = args
+                .project
+                .or_else(|| detect::detect_project(&self.config, &cwd));
+
+            event = event.with_cwd(cwd);
+            if let Some(proj) = project {
+                event = event.with_project(proj);
+            }
         }
+
+        Ok(Some(Pending {
+            event,
+            session: args.session.filter(|id| !id.is_empty()),
+            session_start: args.session_start,
+            resumed_at,
+            explicit_idle,
+        }))
     }
 
-    let session_id = SessionManager::current_session_id_or_create()?;
-    event = event.with_session(session_id);
+    /// Resolves the session for `pending` against `state` without changing it.
+    pub fn commit(&self, pending: Pending, state: &SessionState, now: DateTime<Utc>) -> Logged {
+        let Pending {
+            event,
+            session,
+            session_start,
+            resumed_at,
+            explicit_idle,
+        } = pending;
+
+        let mut changes = Vec::new();
+        let requested = if session_start {
+            None
+        } else {
+            session.or_else(|| state.current_session_id.clone())
+        };
+        let session_id = requested.unwrap_or_else(|| {
+            let session_id = SessionManager::generate_session_id();
+            changes.push(SessionChange::Start(session_id.clone(), now));
+            session_id
+        });
 
-    write_event(&timeline_path, &event)?;
+        let session_end = matches!(event.event_type, EventType::SessionEnd);
+        let mut logged = Logged {
+            session_id: session_id.clone(),
+            events: Vec::new(),
+            changes,
+        };
+
+        if state.active_pause(&session_id, now).is_some() {
+            if session_end {
+                logged.changes.push(SessionChange::End(session_id));
+            }
+            return logged;
+        }
+
+        let event = event.with_session(session_id.clone());
+        let previous = state.sessions.get(&session_id);
 
-    SessionManager::update_last_activity()?;
+        if let Some(info) = previous
+            && !explicit_idle
+        {
+            logged
+                .events
+                .extend(self.idle_gap(&session_id, info.last_activity, resumed_at));
+        }
+
+        let (last_cwd, last_project) = previous
+            .map(|info| (info.last_cwd.as_deref(), info.last_project.as_deref()))
+            .unwrap_or_default();
+        for change in event.location_changes(last_cwd, last_project) {
+            logged.events.push(change.with_timestamp(resumed_at));
+        }
+
+        if session_end {
+            logged.changes.push(SessionChange::End(session_id));
+        } else {
+            logged
+                .changes
+                .push(SessionChange::Touch(session_id.clone(), now));
+            if let Some(ref cwd) = event.cwd {
+                logged.changes.push(SessionChange::Locate(
+                    session_id,
+                    cwd.clone(),
+                    event.project.clone(),
+                ));
+            }
+        }
+
+        logged.events.push(event);
+        logged
+    }
+
+    fn idle_gap(
+        &self,
+        session_id: &str,
+        last_activity: DateTime<Utc>,
+        resumed_at: DateTime<Utc>,
+    ) -> Vec<Event> {
+        activity::idle_gap(last_activity, resumed_at, self.config.idle_timeout_minutes)
+            .map(|(idle_start, idle_end)| {
+                vec![
+                    Event::new(EventType::IdleStart)
+                        .with_timestamp(idle_start)
+                        .with_session(session_id.to_string()),
+                    Event::new(EventType::IdleEnd)
+                        .with_timestamp(idle_end)
+                        .with_session(session_id.to_string()),
+                ]
+            })
+            .unwrap_or_default()
+    }
+}
+
+pub fn log_event(mut args: LogArgs) -> Result<()> {
+    if args.cwd.is_none() {
+        args.cwd = std::env::current_dir()
+            .ok()
+            .map(|p| p.to_string_lossy().to_string());
+    }
+    let print_session_id = args.print_session_id;
+
+    let session_id = match daemon::send_log(&args)? {
+        Some(response) => response.session_id,
+        None => log_direct(args)?,
+    };
+
+    if print_session_id && let Some(session_id) = session_id {
+        println!("{}", session_id);
+    }
 
     Ok(())
 }
 
-pub fn add_note(args: NoteArgs) -> Result<()> {
-    let timeline_path = Config::timeline_path()?;
+fn log_direct(args: LogArgs) -> Result<Option<String>> {
+    let recorder = Recorder::new(Config::load()?)?;
+    let Some(pending) = recorder.prepare(args)? else {
+        return Ok(None);
+    };
 
+    // Append while the state lock is held, so a failed write leaves `state.json` as it was
+    // and a concurrent shell never sees a session whose events are not on disk yet.
+    let logged = SessionManager::record(|state| {
+        let logged = recorder.commit(pending, state, Utc::now());
+        storage::open()?.append_all(&logged.events)?;
+        Ok(logged)
+    })?;
+
+    Ok(Some(logged.session_id))
+}
+
+pub fn add_note(args: NoteArgs) -> Result<()> {
+    let config = Config::load()?;
+    let rules = IgnoreRules::new(&config.ignore)?;
     let cwd = std::env::current_dir()
         .ok()
-        .map(|p| p.to_string_lossy().to_string());
+        .map(|p| p.to_string_lossy().to_string())
+        .filter(|cwd| !rules.ignores_directory(cwd));
 
-    let project = if let Some(ref cwd) = cwd {
-        ProjWarp::resolve_project(cwd)
-    } else {
-        None
-    };
+    let project = cwd
+        .as_deref()
+        .and_then(|cwd| detect::detect_project(&config, cwd));
 
     let mut event = Event::new(EventType::Note {
         text: args.text.clone(),
     });
+    Redactor::new(&config)?.redact_event(&mut event);
 
     if let Some(cwd) = cwd {
         event = event.with_cwd(cwd);
@@ -80,25 +285,16 @@ pub fn add_note(args: NoteArgs) -> Result<()> {
         event = event.with_project(project);
     }
 
-    let session_id = SessionManager::current_session_id_or_create()?;
+    let session_id = SessionManager::resolve(args.session)?;
+    if SessionManager::active_pause(&session_id)?.is_some() {
+        println!("Tracking is paused; note not recorded.");
+        return Ok(());
+    }
     event = event.with_session(session_id);
 
-    write_event(&timeline_path, &event)?;
+    storage::open()?.append(&event)?;
 
     println!("Note added: {}", args.text);
 
     Ok(())
 }
-
-fn write_event(path: &std::path::Path, event: &Event) -> Result<()> {
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-
-    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
-
-    let json = serde_json::to_string(event)?;
-    writeln!(file, "{}", json)?;
-
-    Ok(())
-}
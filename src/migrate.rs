@@ -0,0 +1,209 @@
+use crate::cli::MigrateArgs;
+use crate::config::Config;
+use crate::events::{Event, SCHEMA_VERSION};
+use crate::pruner;
+use crate::segments::SegmentedStore;
+use crate::utils;
+use anyhow::{Context, Result, anyhow};
+use colored::*;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+const CONTENT_ID_NAMESPACE: Uuid = Uuid::from_u128(0x6f70_7374_7261_696c_8000_0000_0000_0001);
+
+type Migration = fn(&mut Map<String, Value>) -> Result<()>;
+
+// MIGRATIONS[n] upgrades a record from schema version n + 1 to n + 2.
+const MIGRATIONS: [Migration; 1] = [v1_to_v2];
+
+pub fn schema_version(value: &Value) -> u32 {
+    value
+        .get("schema")
+        .and_then(Value::as_u64)
+        .map_or(1, |version| version as u32)
+}
+
+pub fn upgrade(value: &mut Value) -> Result<()> {
+    let version = schema_version(value);
+    if version > SCHEMA_VERSION {
+        return Err(anyhow!(
+            "Event schema version {} is newer than this trail supports ({})",
+            version,
+            SCHEMA_VERSION
+        ));
+    }
+
+    let record = value
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("Event is not a JSON object"))?;
+
+    for migration in &MIGRATIONS[(version as usize).saturating_sub(1)..] {
+        migration(record)?;
+    }
+    record.insert("schema".to_string(), Value::from(SCHEMA_VERSION));
+
+    Ok(())
+}
+
+fn v1_to_v2(record: &mut Map<String, Value>) -> Result<()> {
+    if !record.contains_key("id") {
+        let content = serde_json::to_vec(record)?;
+        let id = Uuid::new_v5(&CONTENT_ID_NAMESPACE, &content);
+        record.insert("id".to_string(), Value::from(id.to_string()));
+    }
+    Ok(())
+}
+
+pub struct BadLine {
+    pub line: usize,
+    pub error: String,
+}
+
+pub struct FileMigration {
+    pub path: PathBuf,
+    pub lines: Vec<String>,
+    pub migrated: usize,
+    pub bad: Vec<BadLine>,
+}
+
+pub fn migrate_file(path: &Path) -> Result<FileMigration> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut lines = Vec::new();
+    let mut migrated = 0usize;
+    let mut bad = Vec::new();
+
+    for (n, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let parsed = serde_json::from_str::<Value>(line)
+            .map_err(anyhow::Error::from)
+            .and_then(|value| Ok((schema_version(&value), Event::try_from(value)?)));
+
+        match parsed {
+            Ok((version, event)) if version < SCHEMA_VERSION => {
+                lines.push(serde_json::to_string(&event)?);
+                migrated += 1;
+            }
+            Ok(_) => lines.push(line.to_string()),
+            Err(e) => {
+                bad.push(BadLine {
+                    line: n + 1,
+                    error: e.to_string(),
+                });
+                lines.push(line.to_string());
+            }
+        }
+    }
+
+    Ok(FileMigration {
+        path: path.to_path_buf(),
+        lines,
+        migrated,
+        bad,
+    })
+}
+
+pub fn migrate(args: MigrateArgs) -> Result<()> {
+    let legacy = Config::timeline_path()?;
+    let store = SegmentedStore::new(Config::timeline_dir()?);
+    if !args.dry_run && legacy.exists() {
+        let moved = store.split_legacy(&legacy)?;
+        println!(
+            "Moved {} lines from {} into monthly segments in {}",
+            moved.to_string().yellow(),
+            legacy.display().to_string().dimmed(),
+            store.dir().display().to_string().dimmed()
+        );
+    }
+
+    let _lock = store.lock()?;
+
+    let mut paths = store.segment_paths()?;
+    if legacy.exists() {
+        paths.push(legacy);
+    }
+    paths.extend(pruner::archive_paths(&Config::data_dir()?)?);
+
+    let results = paths
+        .iter()
+        .map(|path| migrate_file(path))
+        .collect::<Result<Vec<_>>>()?;
+
+    let migrated: usize = results.iter().map(|r| r.migrated).sum();
+    let bad: usize = results.iter().map(|r| r.bad.len()).sum();
+
+    for result in results.iter().filter(|r| r.migrated > 0) {
+        println!(
+            "{} events in {}",
+            result.migrated.to_string().yellow(),
+            result.path.display().to_string().dimmed()
+        );
+    }
+
+    if bad > 0 {
+        println!("{}", "Unparseable lines (left unchanged):".bold().red());
+        for result in &results {
+            for line in &result.bad {
+                println!(
+                    "  {}:{}: {}",
+                    result.path.display(),
+                    line.line,
+                    line.error.dimmed()
+                );
+            }
+        }
+    }
+
+    if migrated == 0 {
+        println!(
+            "History is already at schema version {}.",
+            SCHEMA_VERSION.to_string().green()
+        );
+        return Ok(());
+    }
+
+    if args.dry_run {
+        println!(
+            "{} {} events would be migrated to schema version {}.",
+            "[dry-run]".yellow(),
+            migrated.to_string().yellow(),
+            SCHEMA_VERSION
+        );
+        return Ok(());
+    }
+
+    let contents: Vec<(&Path, String)> = results
+        .iter()
+        .filter(|r| r.migrated > 0)
+        .map(|r| {
+            let mut text = String::new();
+            for line in &r.lines {
+                text.push_str(line);
+                text.push('\n');
+            }
+            (r.path.as_path(), text)
+        })
+        .collect();
+    let files: Vec<(&Path, &[u8])> = contents
+        .iter()
+        .map(|(path, text)| (*path, text.as_bytes()))
+        .collect();
+    utils::write_atomic_all(&files)?;
+    for result in results.iter().filter(|r| r.migrated > 0) {
+        store.invalidate(&result.path)?;
+    }
+
+    println!(
+        "Migrated {} events to schema version {}.",
+        migrated.to_string().yellow(),
+        SCHEMA_VERSION
+    );
+
+    Ok(())
+}
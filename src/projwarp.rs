@@ -1,16 +1,24 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The contents of `~/.projwarp.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ProjWarpConfig {
-    pub projects: HashMap<String, String>,
+    /// Project roots by alias.
+    pub projects: BTreeMap<String, String>,
 }
 
+static RESOLVER: RwLock<Option<Arc<ProjectResolver>>> = RwLock::new(None);
+
+/// Access to the ProjWarp project list in `~/.projwarp.json`.
 pub struct ProjWarp;
 
 impl ProjWarp {
+    /// Reads `~/.projwarp.json`, or `None` if it is missing or cannot be parsed.
     pub fn load() -> Option<ProjWarpConfig> {
         let path = Self::config_path()?;
 
@@ -22,33 +30,133 @@ impl ProjWarp {
         serde_json::from_str(&contents).ok()
     }
 
+    /// A resolver for the project list, read on first use and kept until
+    /// [`ProjWarp::reload`].
+    pub fn resolver() -> Arc<ProjectResolver> {
+        if let Some(resolver) = RESOLVER
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            return Arc::clone(resolver);
+        }
+
+        let mut slot = RESOLVER.write().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(slot.get_or_insert_with(|| {
+            Arc::new(ProjectResolver::new(&Self::load().unwrap_or_default()))
+        }))
+    }
+
+    /// Drops the cached project list so the next lookup reads `~/.projwarp.json` again.
+    pub fn reload() {
+        *RESOLVER.write().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// The alias of the ProjWarp project containing `path`, if any.
     pub fn resolve_project(path: &str) -> Option<String> {
-        let config = Self::load()?;
+        Self::resolver().resolve(path)
+    }
 
-        let normalized_path = path.replace('\\', "/");
+    pub(crate) fn config_path() -> Option<PathBuf> {
+        let home = dirs::home_dir()?;
+        Some(home.join(".projwarp.json"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Root {
+    path: String,
+    alias: String,
+}
+
+/// Maps paths to ProjWarp aliases, preferring the deepest matching root.
+#[derive(Debug, Default)]
+pub struct ProjectResolver {
+    roots: Vec<Root>,
+}
+
+impl ProjectResolver {
+    /// Builds a resolver for the roots in `config`.
+    pub fn new(config: &ProjWarpConfig) -> Self {
+        let mut roots = Vec::new();
 
         for (alias, project_path) in &config.projects {
-            let normalized_project = project_path.replace('\\', "/");
-            if normalized_path == normalized_project {
-                return Some(alias.clone());
+            for path in path_forms(project_path) {
+                roots.push(Root {
+                    path,
+                    alias: alias.clone(),
+                });
             }
         }
 
-        for (alias, project_path) in &config.projects {
-            let normalized_project = project_path.replace('\\', "/");
-            if normalized_path.starts_with(&normalized_project)
-                && (normalized_path.len() == normalized_project.len()
-                    || normalized_path[normalized_project.len()..].starts_with('/'))
-            {
-                return Some(alias.clone());
-            }
+        roots.sort_by(|a, b| {
+            (Reverse(a.path.len()), &a.alias, &a.path).cmp(&(
+                Reverse(b.path.len()),
+                &b.alias,
+                &b.path,
+            ))
+        });
+        roots.dedup();
+
+        Self { roots }
+    }
+
+    /// The alias of the deepest root containing `path`, if any.
+    pub fn resolve(&self, path: &str) -> Option<String> {
+        let candidates = path_forms(path);
+
+        self.roots
+            .iter()
+            .find(|root| {
+                candidates
+                    .iter()
+                    .any(|candidate| is_within(candidate, &root.path))
+            })
+            .map(|root| root.alias.clone())
+    }
+}
+
+fn path_forms(path: &str) -> Vec<String> {
+    let mut forms = vec![normalize(path)];
+
+    if let Ok(canonical) = fs::canonicalize(Path::new(path)) {
+        let canonical = normalize(&canonical.to_string_lossy());
+        if !forms.contains(&canonical) {
+            forms.push(canonical);
         }
+    }
+
+    forms
+}
 
-        None
+fn normalize(path: &str) -> String {
+    let mut normalized = path.replace('\\', "/");
+
+    if let Some(stripped) = normalized.strip_prefix("//?/") {
+        normalized = stripped.to_string();
     }
 
-    fn config_path() -> Option<PathBuf> {
-        let home = dirs::home_dir()?;
-        Some(home.join(".projwarp.json"))
+    while normalized.len() > 1 && normalized.ends_with('/') && !is_drive_root(&normalized) {
+        normalized.pop();
+    }
+
+    if cfg!(windows) || has_drive_prefix(&normalized) {
+        normalized = normalized.to_lowercase();
     }
+
+    normalized
+}
+
+fn has_drive_prefix(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+fn is_drive_root(path: &str) -> bool {
+    path.len() == 3 && has_drive_prefix(path)
+}
+
+fn is_within(path: &str, root: &str) -> bool {
+    path == root
+        || (path.starts_with(root) && (root.ends_with('/') || path[root.len()..].starts_with('/')))
 }
@@ -1,110 +1,108 @@
 use crate::cli::PruneArgs;
-use crate::config::Config;
-use anyhow::{Context, Result};
+use crate::segments::{Segment, SegmentedStore};
+use crate::storage::EventStore;
+use crate::utils;
+use anyhow::Result;
 use chrono::Local;
 use colored::*;
 use std::fs;
-use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
 
 pub fn prune(args: PruneArgs) -> Result<()> {
-    let timeline_path = Config::timeline_path()?;
+    let store = SegmentedStore::open()?;
 
-    if !timeline_path.exists() {
+    if !store.exists() {
         println!("No activity history found.");
         return Ok(());
     }
 
-    let cutoff = Local::now().date_naive() - chrono::Duration::days(args.keep_days as i64);
-
-    let file = fs::File::open(&timeline_path)?;
-    let reader = BufReader::new(file);
-
-    let mut kept = Vec::new();
-    let mut pruned_count = 0usize;
-
-    for line in reader.lines() {
-        let line = line?;
-        if line.trim().is_empty() {
-            continue;
-        }
-
-        let keep = if let Ok(event) = serde_json::from_str::<crate::events::Event>(&line) {
-            let date = event.timestamp.with_timezone(&Local).date_naive();
-            date >= cutoff
-        } else {
-            true
-        };
-
-        if keep {
-            kept.push(line);
-        } else {
-            pruned_count += 1;
-        }
-    }
-
-    if pruned_count == 0 {
+    let cutoff_date = Local::now().date_naive() - chrono::Duration::days(args.keep_days as i64);
+    let cutoff = utils::local_day_start(cutoff_date);
+
+    // Hold the lock from the manifest read to the deletes, so a month that gains events
+    // in between is not removed with them.
+    let lock = if args.dry_run {
+        None
+    } else {
+        Some(store.lock()?)
+    };
+    let manifest = if lock.is_some() {
+        store.locked_manifest()?
+    } else {
+        store.manifest()?
+    };
+    let (expired, kept): (Vec<Segment>, Vec<Segment>) = manifest
+        .segments
+        .into_iter()
+        .partition(|segment| segment.last.is_some_and(|last| last < cutoff));
+    let pruned_count: usize = expired.iter().map(|s| s.events).sum();
+    let kept_count: usize = kept.iter().map(|s| s.events).sum();
+
+    if expired.is_empty() {
         println!(
-            "Nothing to prune (no events older than {} days).",
+            "Nothing to prune (no month of history is entirely older than {} days).",
             args.keep_days
         );
         return Ok(());
     }
 
     if args.dry_run {
+        for segment in &expired {
+            println!(
+                "{} {} ({} events)",
+                "[dry-run]".yellow(),
+                segment.month,
+                segment.events
+            );
+        }
         println!(
-            "{} {} events would be pruned (older than {}).",
+            "{} {} events in {} months would be permanently deleted (older than {}).",
             "[dry-run]".yellow(),
             pruned_count.to_string().yellow(),
-            cutoff.format("%Y-%m-%d")
+            expired.len(),
+            cutoff_date.format("%Y-%m-%d")
         );
         println!(
             "{} {} events would be retained.",
             "[dry-run]".yellow(),
-            kept.len().to_string().green()
+            kept_count.to_string().green()
         );
         return Ok(());
     }
 
-    let data_dir = Config::data_dir()?;
-    let archive_name = format!(
-        "timeline-archive-{}.jsonl",
-        Local::now().format("%Y%m%d%H%M%S")
-    );
-    let archive_path = data_dir.join(&archive_name);
-
-    let original = fs::read_to_string(&timeline_path)?;
-    fs::write(&archive_path, &original)?;
-
-    let mut out = fs::OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(&timeline_path)
-        .with_context(|| {
-            format!(
-                "Failed to write pruned timeline. Original data archived at: {}",
-                archive_path.display()
-            )
-        })?;
-
-    for line in &kept {
-        writeln!(out, "{}", line).with_context(|| {
-            format!(
-                "Failed to write pruned timeline. Original data archived at: {}",
-                archive_path.display()
-            )
-        })?;
+    for segment in &expired {
+        store.remove_segment(&segment.month)?;
     }
 
     println!(
-        "Pruned {} events older than {}.",
+        "Permanently deleted {} events in {} months older than {}. No archive is kept.",
         pruned_count.to_string().yellow(),
-        cutoff.format("%Y-%m-%d")
-    );
-    println!("Retained {} events.", kept.len().to_string().green());
-    println!(
-        "Archive saved to: {}",
-        archive_path.display().to_string().dimmed()
+        expired.len(),
+        cutoff_date.format("%Y-%m-%d")
     );
+    println!("Retained {} events.", kept_count.to_string().green());
 
     Ok(())
 }
+
+/// Prune archives written by earlier versions, which copied the whole timeline.
+pub fn archive_paths(data_dir: &Path) -> Result<Vec<PathBuf>> {
+    if !data_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut archives = Vec::new();
+    for entry in fs::read_dir(data_dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if name.starts_with("timeline-archive-") && name.ends_with(".jsonl") {
+            archives.push(path);
+        }
+    }
+
+    archives.sort();
+    Ok(archives)
+}
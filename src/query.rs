@@ -1,34 +1,133 @@
-use crate::cli::{BackArgs, SearchArgs, StatsArgs, TimelineArgs};
+use crate::activity;
+use crate::cli::{BackArgs, OutputArgs, OutputFormat, SearchArgs, StatsArgs, TimelineArgs};
 use crate::config::Config;
 use crate::events::{Event, EventType};
 use crate::projwarp::ProjWarp;
+use crate::session;
+use crate::storage::{self, EventFilter, EventStore, Summary};
 use crate::utils;
 use anyhow::Result;
-use chrono::{Datelike, Local, NaiveDate};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
 use colored::*;
-use std::collections::HashMap;
-use std::fs;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+const SEARCH_DISPLAY_LIMIT: usize = 50;
+const TOP_PROJECTS: usize = 5;
+const TOP_COMMANDS: usize = 10;
+const SLOWEST_LIMIT: usize = 5;
+
+/// Active time spent in one project.
+#[derive(Debug, Serialize)]
+pub struct ProjectTime {
+    /// The project name.
+    pub project: String,
+    /// Active seconds.
+    pub active_secs: i64,
+}
+
+/// Active time on one local day.
+#[derive(Debug, Serialize)]
+pub struct DayTime {
+    /// The local date.
+    pub date: NaiveDate,
+    /// Active seconds.
+    pub active_secs: i64,
+}
+
+/// How often a program was run.
+#[derive(Debug, Serialize)]
+pub struct CommandCount {
+    /// The first word of the command line.
+    pub command: String,
+    /// Number of runs.
+    pub count: usize,
+}
+
+/// A command and how long it ran.
+#[derive(Debug, Serialize)]
+pub struct SlowCommand {
+    /// The command line.
+    pub cmd: String,
+    /// How long it ran.
+    pub duration_ms: u64,
+}
+
+/// The report printed by `trail stats`. Durations are whole seconds.
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    /// First local day of the period.
+    pub from: NaiveDate,
+    /// Last local day of the period.
+    pub to: NaiveDate,
+    /// Events in the period.
+    pub events: usize,
+    /// Commands in the period.
+    pub commands: usize,
+    /// Commands with a non-zero exit code.
+    pub failed_commands: usize,
+    /// Days with any active time.
+    pub active_days: usize,
+    /// Active time across the period.
+    pub active_secs: i64,
+    /// Mean active time per session, if there were any sessions.
+    pub average_session_secs: Option<i64>,
+    /// Time between idle start and idle end events.
+    pub idle_secs: i64,
+    /// Projects by active time, most first.
+    pub projects: Vec<ProjectTime>,
+    /// Active time for each day of the period.
+    pub days: Vec<DayTime>,
+    /// Most run programs, most first.
+    pub top_commands: Vec<CommandCount>,
+    /// Longest running commands, slowest first.
+    pub slowest: Vec<SlowCommand>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TodaySummary {
+    pub date: NaiveDate,
+    pub events: usize,
+    pub commands: usize,
+    pub active_secs: i64,
+    pub idle_secs: i64,
+    pub paused_secs: i64,
+    pub projects: Vec<ProjectTime>,
+    pub tracking: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectActivity {
+    pub project: String,
+    pub events: usize,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+    pub last_cwd: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResumePoint {
+    pub cwd: String,
+    pub project: Option<String>,
+    pub session_id: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub last_command: Option<String>,
+    pub paused_at: Option<DateTime<Utc>>,
+    pub paused_until: Option<DateTime<Utc>>,
+    pub tracking: Option<String>,
+}
 
 pub fn time_travel(args: BackArgs) -> Result<()> {
-    let timeline_path = Config::timeline_path()?;
+    let store = storage::open()?;
 
-    if !timeline_path.exists() {
+    if !store.exists() {
         println!("No activity history found.");
         return Ok(());
     }
 
-    let contents = fs::read_to_string(&timeline_path)?;
-    let events: Vec<Event> = contents
-        .lines()
-        .filter_map(|line| serde_json::from_str(line).ok())
-        .collect();
-
     let target_time = utils::parse_relative_time(&args.when)?;
 
-    let target_event = events
-        .iter()
-        .filter(|e| e.cwd.is_some() && e.timestamp <= target_time)
-        .max_by_key(|e| e.timestamp);
+    let target_event = store.last(&EventFilter::until(target_time), &|e| e.cwd.is_some())?;
 
     if let Some(event) = target_event {
         if let Some(ref cwd) = event.cwd {
@@ -52,62 +151,49 @@ pub fn time_travel(args: BackArgs) -> Result<()> {
 }
 
 pub fn search(args: SearchArgs) -> Result<()> {
-    let timeline_path = Config::timeline_path()?;
+    let store = storage::open()?;
+    let format = args.output.format;
 
-    if !timeline_path.exists() {
+    if format == OutputFormat::Text && !store.exists() {
         println!("No activity history found.");
         return Ok(());
     }
 
-    let contents = fs::read_to_string(&timeline_path)?;
-    let events: Vec<Event> = contents
-        .lines()
-        .filter_map(|line| serde_json::from_str(line).ok())
-        .collect();
-
-    let query_lower = args.query.to_lowercase();
+    let filter = EventFilter::from_args(&args.filter)?;
+    let results = search_events(store.as_ref(), &filter, &args.query)?;
 
-    let filtered: Vec<&Event> = events
-        .iter()
-        .filter(|e| {
-            if args.today {
-                let today = Local::now().date_naive();
-                if e.timestamp.with_timezone(&Local).date_naive() != today {
-                    return false;
-                }
-            }
-
-            if let Some(ref date_str) = args.date {
-                if let Ok(target_date) = NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-                    if e.timestamp.with_timezone(&Local).date_naive() != target_date {
-                        return false;
-                    }
-                }
-            }
+    match format {
+        OutputFormat::Text => print_search(&args.query, &results),
+        _ => print!("{}", utils::json_stream(format, &results)?),
+    }
 
-            if let Some(ref proj) = args.project {
-                if e.project.as_ref() != Some(proj) {
-                    return false;
-                }
-            }
+    Ok(())
+}
 
-            match &e.event_type {
-                EventType::Command { cmd } => cmd.to_lowercase().contains(&query_lower),
-                EventType::Note { text } => text.to_lowercase().contains(&query_lower),
-                EventType::ProjectDetected { name } => name.to_lowercase().contains(&query_lower),
-                _ => false,
-            }
-        })
-        .collect();
+/// Events matching `filter` whose command, note or project contains `query`, in file order.
+pub fn search_events(
+    store: &dyn EventStore,
+    filter: &EventFilter,
+    query: &str,
+) -> Result<Vec<Event>> {
+    let mut results = Vec::new();
+    store.for_each(filter, &mut |event| {
+        if event.matches_text(query) {
+            results.push(event);
+        }
+    })?;
+    Ok(results)
+}
 
-    if filtered.is_empty() {
-        println!("No results found for '{}'", args.query);
-        return Ok(());
+fn print_search(query: &str, results: &[Event]) {
+    if results.is_empty() {
+        println!("No results found for '{}'", query);
+        return;
     }
 
-    println!("Found {} results:\n", filtered.len());
+    println!("Found {} results:\n", results.len());
 
-    for event in filtered.iter().take(50) {
+    for event in results.iter().take(SEARCH_DISPLAY_LIMIT) {
         let time = event
             .timestamp
             .with_timezone(&Local)
@@ -119,186 +205,294 @@ pub fn search(args: SearchArgs) -> Result<()> {
             .unwrap_or_default();
 
         let description = match &event.event_type {
-            EventType::Command { cmd } => format!("ran {}", cmd.yellow()),
+            EventType::Command {
+                cmd,
+                exit_code,
+                duration_ms,
+                ..
+            } => format!(
+                "ran {}{}",
+                cmd.yellow(),
+                command_outcome(*exit_code, *duration_ms)
+            ),
             EventType::Note { text } => format!("note: {}", text.green()),
             EventType::ProjectDetected { name } => format!("entered project {}", name.cyan()),
             _ => continue,
         };
 
         println!(
-            "{} {} {}",
+            "{} {} {} {}",
             time.to_string().dimmed(),
+            event.short_id().dimmed(),
             project_tag,
             description
         );
     }
-
-    Ok(())
 }
 
 pub fn stats(args: StatsArgs) -> Result<()> {
-    let timeline_path = Config::timeline_path()?;
+    let store = storage::open()?;
+    let format = args.output.format;
 
-    if !timeline_path.exists() {
+    if format == OutputFormat::Text && !store.exists() {
         println!("No activity history found.");
         return Ok(());
     }
 
-    let contents = fs::read_to_string(&timeline_path)?;
-    let all_events: Vec<Event> = contents
-        .lines()
-        .filter_map(|line| serde_json::from_str(line).ok())
-        .collect();
+    let (from, to) = stats_period(&args, Local::now().date_naive())?;
+    let events = store.scan(&EventFilter::dates(from, to))?;
 
-    let now = Local::now();
+    if format == OutputFormat::Text && events.is_empty() {
+        println!("No activity found for the specified period.");
+        return Ok(());
+    }
 
-    let (from, to) = if args.week {
-        let days_from_monday = now.date_naive().weekday().num_days_from_monday() as i64;
-        let start = now.date_naive() - chrono::Duration::days(days_from_monday);
-        let end = now.date_naive();
-        (start, end)
-    } else if args.month {
-        let start = NaiveDate::from_ymd_opt(now.year(), now.month(), 1).unwrap();
-        let end = now.date_naive();
-        (start, end)
-    } else if let (Some(from_str), Some(to_str)) = (&args.from, &args.to) {
-        let from = NaiveDate::parse_from_str(from_str, "%Y-%m-%d")
-            .map_err(|_| anyhow::anyhow!("Invalid --from date format. Use YYYY-MM-DD"))?;
-        let to = NaiveDate::parse_from_str(to_str, "%Y-%m-%d")
-            .map_err(|_| anyhow::anyhow!("Invalid --to date format. Use YYYY-MM-DD"))?;
-        (from, to)
-    } else if let Some(from_str) = &args.from {
-        let from = NaiveDate::parse_from_str(from_str, "%Y-%m-%d")
-            .map_err(|_| anyhow::anyhow!("Invalid --from date format. Use YYYY-MM-DD"))?;
-        (from, now.date_naive())
-    } else {
-        let thirty_days_ago = now.date_naive() - chrono::Duration::days(30);
-        (thirty_days_ago, now.date_naive())
+    let config = Config::load()?;
+    let stats = compute_stats(&events, from, to, config.idle_timeout_minutes);
+
+    match format {
+        OutputFormat::Text => print_stats(&stats),
+        _ => print!("{}", utils::json_document(format, &stats)?),
+    }
+
+    Ok(())
+}
+
+/// The inclusive date range selected by `trail stats` flags, relative to `today`.
+pub fn stats_period(args: &StatsArgs, today: NaiveDate) -> Result<(NaiveDate, NaiveDate)> {
+    let parse = |input: &str, flag: &str| {
+        NaiveDate::parse_from_str(input, "%Y-%m-%d")
+            .map_err(|_| anyhow::anyhow!("Invalid --{} date format. Use YYYY-MM-DD", flag))
     };
 
-    let events: Vec<&Event> = all_events
-        .iter()
-        .filter(|e| {
-            let date = e.timestamp.with_timezone(&Local).date_naive();
-            date >= from && date <= to
-        })
-        .collect();
+    Ok(if args.week {
+        let days_from_monday = today.weekday().num_days_from_monday() as i64;
+        (today - Duration::days(days_from_monday), today)
+    } else if args.month {
+        (today.with_day(1).unwrap(), today)
+    } else if let (Some(from), Some(to)) = (&args.from, &args.to) {
+        (parse(from, "from")?, parse(to, "to")?)
+    } else if let Some(from) = &args.from {
+        (parse(from, "from")?, today)
+    } else {
+        (today - Duration::days(30), today)
+    })
+}
 
-    if events.is_empty() {
-        println!("No activity found for the specified period.");
-        return Ok(());
-    }
+/// Computes [`Stats`] for events between local days `from` and `to`.
+pub fn compute_stats(
+    events: &[Event],
+    from: NaiveDate,
+    to: NaiveDate,
+    idle_timeout_minutes: u64,
+) -> Stats {
+    let active = activity::active_time(events, idle_timeout_minutes);
+    let timed = || events.iter().filter(|e| !e.has_synthesized_time());
 
-    let mut project_time: HashMap<String, i64> = HashMap::new();
     let mut command_count: HashMap<String, usize> = HashMap::new();
-    let mut total_commands = 0usize;
-    let mut active_days: std::collections::HashSet<NaiveDate> = std::collections::HashSet::new();
+    let mut commands = 0usize;
+    let mut failed_commands = 0usize;
+    let mut slowest: Vec<SlowCommand> = Vec::new();
+    let mut active_days: HashSet<NaiveDate> = HashSet::new();
 
-    for event in &events {
+    for event in timed() {
         active_days.insert(event.timestamp.with_timezone(&Local).date_naive());
 
-        if let EventType::Command { cmd } = &event.event_type {
-            total_commands += 1;
+        if let EventType::Command {
+            cmd,
+            exit_code,
+            duration_ms,
+            ..
+        } = &event.event_type
+        {
+            commands += 1;
             let cmd_name = cmd.split_whitespace().next().unwrap_or(cmd);
             *command_count.entry(cmd_name.to_string()).or_insert(0) += 1;
-        }
 
-        if let Some(ref project) = event.project {
-            *project_time.entry(project.clone()).or_insert(0) += 1;
+            if exit_code.is_some_and(|code| code != 0) {
+                failed_commands += 1;
+            }
+            if let Some(ms) = duration_ms {
+                slowest.push(SlowCommand {
+                    cmd: cmd.clone(),
+                    duration_ms: *ms,
+                });
+            }
         }
     }
 
+    let mut top_commands: Vec<CommandCount> = command_count
+        .into_iter()
+        .map(|(command, count)| CommandCount { command, count })
+        .collect();
+    top_commands.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.command.cmp(&b.command))
+    });
+
+    slowest.sort_by_key(|slow| std::cmp::Reverse(slow.duration_ms));
+    slowest.truncate(SLOWEST_LIMIT);
+
+    let average_session_secs = (!active.by_session.is_empty())
+        .then(|| active.total.num_seconds() / active.by_session.len() as i64);
+
+    Stats {
+        from,
+        to,
+        events: timed().count(),
+        commands,
+        failed_commands,
+        active_days: active_days.len(),
+        active_secs: active.total.num_seconds(),
+        average_session_secs,
+        idle_secs: activity::total_idle(events).num_seconds(),
+        projects: project_times(&active.by_project),
+        days: active
+            .by_day
+            .iter()
+            .map(|(date, time)| DayTime {
+                date: *date,
+                active_secs: time.num_seconds(),
+            })
+            .collect(),
+        top_commands,
+        slowest,
+    }
+}
+
+fn print_stats(stats: &Stats) {
     println!("{}", "Activity Statistics".bold().cyan());
     println!(
         "  Period: {} to {}",
-        from.format("%Y-%m-%d").to_string().yellow(),
-        to.format("%Y-%m-%d").to_string().yellow()
+        stats.from.format("%Y-%m-%d").to_string().yellow(),
+        stats.to.format("%Y-%m-%d").to_string().yellow()
     );
     println!();
     println!(
         "  {:<25} {}",
         "Total events:",
-        events.len().to_string().yellow()
+        stats.events.to_string().yellow()
     );
     println!(
         "  {:<25} {}",
         "Total commands:",
-        total_commands.to_string().yellow()
+        stats.commands.to_string().yellow()
+    );
+    println!(
+        "  {:<25} {}",
+        "Failed commands:",
+        stats.failed_commands.to_string().red()
     );
     println!(
         "  {:<25} {}",
         "Active days:",
-        active_days.len().to_string().yellow()
+        stats.active_days.to_string().yellow()
+    );
+    println!(
+        "  {:<25} {}",
+        "Active time:",
+        format_secs(stats.active_secs).yellow()
+    );
+    if let Some(average) = stats.average_session_secs {
+        println!("  {:<25} {}", "Avg session:", format_secs(average).yellow());
+    }
+    println!(
+        "  {:<25} {}",
+        "Idle time:",
+        format_secs(stats.idle_secs).dimmed()
     );
     println!();
 
     println!("{}", "Most Active Projects:".bold());
-    let mut projects: Vec<_> = project_time.iter().collect();
-    projects.sort_by(|a, b| b.1.cmp(a.1));
-    for (i, (project, count)) in projects.iter().take(5).enumerate() {
-        println!("  {}. {} ({} activities)", i + 1, project.yellow(), count);
+    for (i, project) in stats.projects.iter().take(TOP_PROJECTS).enumerate() {
+        println!(
+            "  {}. {} ({})",
+            i + 1,
+            project.project.yellow(),
+            format_secs(project.active_secs)
+        );
+    }
+    println!();
+
+    println!("{}", "Time by Day:".bold());
+    for day in stats.days.iter().rev() {
+        println!(
+            "  {} {}",
+            day.date.format("%Y-%m-%d %a").to_string().dimmed(),
+            format_secs(day.active_secs)
+        );
     }
     println!();
 
     println!("{}", "Most Used Commands:".bold());
-    let mut commands: Vec<_> = command_count.iter().collect();
-    commands.sort_by(|a, b| b.1.cmp(a.1));
-    for (i, (cmd, count)) in commands.iter().take(10).enumerate() {
-        println!("  {}. {} ({})", i + 1, cmd.green(), count);
+    for (i, command) in stats.top_commands.iter().take(TOP_COMMANDS).enumerate() {
+        println!(
+            "  {}. {} ({})",
+            i + 1,
+            command.command.green(),
+            command.count
+        );
     }
 
-    Ok(())
+    if !stats.slowest.is_empty() {
+        println!();
+        println!("{}", "Slowest Commands:".bold());
+        for (i, slow) in stats.slowest.iter().enumerate() {
+            println!(
+                "  {}. {} ({})",
+                i + 1,
+                slow.cmd.green(),
+                utils::format_duration(Duration::milliseconds(slow.duration_ms as i64))
+            );
+        }
+    }
 }
 
 pub fn timeline(args: TimelineArgs) -> Result<()> {
-    let timeline_path = Config::timeline_path()?;
+    let store = storage::open()?;
+    let format = args.output.format;
 
-    if !timeline_path.exists() {
+    if format == OutputFormat::Text && !store.exists() {
         println!("No activity history found.");
         return Ok(());
     }
 
-    let contents = fs::read_to_string(&timeline_path)?;
-    let events: Vec<Event> = contents
-        .lines()
-        .filter_map(|line| serde_json::from_str(line).ok())
-        .collect();
-
-    let filtered: Vec<&Event> = if args.today {
-        let today = Local::now().date_naive();
-        events
-            .iter()
-            .filter(|e| e.timestamp.with_timezone(&Local).date_naive() == today)
-            .collect()
+    let filter = if args.today {
+        EventFilter::on_date(Local::now().date_naive())
     } else if args.yesterday {
-        let yesterday = Local::now().date_naive().pred_opt().unwrap();
-        events
-            .iter()
-            .filter(|e| e.timestamp.with_timezone(&Local).date_naive() == yesterday)
-            .collect()
+        EventFilter::on_date(Local::now().date_naive().pred_opt().unwrap())
     } else if let Some(ref date_str) = args.date {
         if let Ok(target_date) = NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-            events
-                .iter()
-                .filter(|e| e.timestamp.with_timezone(&Local).date_naive() == target_date)
-                .collect()
+            EventFilter::on_date(target_date)
         } else {
             eprintln!("Invalid date format. Use YYYY-MM-DD");
             return Ok(());
         }
     } else {
-        events.iter().collect()
+        EventFilter::all()
     };
 
-    if filtered.is_empty() {
+    let events = store.recent(&filter, args.limit)?;
+
+    match format {
+        OutputFormat::Text => print_timeline(&events),
+        _ => print!("{}", utils::json_stream(format, &events)?),
+    }
+
+    Ok(())
+}
+
+fn print_timeline(events: &[Event]) {
+    if events.is_empty() {
         println!("No activity found for the specified period.");
-        return Ok(());
+        return;
     }
 
     println!("{}", "Activity Timeline".bold().cyan());
     println!();
 
-    for event in filtered.iter().rev().take(args.limit) {
+    for event in events {
         let time = event
             .timestamp
             .with_timezone(&Local)
@@ -310,7 +504,16 @@ pub fn timeline(args: TimelineArgs) -> Result<()> {
             .unwrap_or_else(|| "".to_string());
 
         let label = match &event.event_type {
-            EventType::Command { cmd } => format!("cmd  {}", cmd.yellow()),
+            EventType::Command {
+                cmd,
+                exit_code,
+                duration_ms,
+                ..
+            } => format!(
+                "cmd  {}{}",
+                cmd.yellow(),
+                command_outcome(*exit_code, *duration_ms)
+            ),
             EventType::DirectoryChange { to, .. } => format!("cd   {}", to.blue()),
             EventType::SessionStart => format!("sess {}", "started".green()),
             EventType::SessionEnd => format!("sess {}", "ended".red()),
@@ -318,155 +521,258 @@ pub fn timeline(args: TimelineArgs) -> Result<()> {
             EventType::IdleEnd => format!("idle {}", "end".green()),
             EventType::Note { text } => format!("note {}", text.green()),
             EventType::ProjectDetected { name } => format!("proj {}", name.cyan()),
+            EventType::Paused { until } => {
+                format!("trck {}{}", "paused".yellow(), until_label(*until))
+            }
+            EventType::Unpaused => format!("trck {}", "resumed".green()),
         };
 
-        println!("{} {} {}", time.to_string().dimmed(), project, label);
+        println!(
+            "{} {} {} {}",
+            time.to_string().dimmed(),
+            event.short_id().dimmed(),
+            project,
+            label
+        );
     }
-
-    Ok(())
 }
 
-pub fn resume() -> Result<()> {
-    let timeline_path = Config::timeline_path()?;
+pub fn resume(args: OutputArgs) -> Result<()> {
+    let store = storage::open()?;
+    let format = args.format;
 
-    if !timeline_path.exists() {
+    if format == OutputFormat::Text && !store.exists() {
         println!("No activity history found.");
         return Ok(());
     }
 
-    let contents = fs::read_to_string(&timeline_path)?;
-    let events: Vec<Event> = contents
-        .lines()
-        .filter_map(|line| serde_json::from_str(line).ok())
-        .collect();
+    let mut point = resume_point(store.as_ref())?;
+    if let Some(ref mut point) = point {
+        point.tracking = session::pause_status()?;
+    }
 
-    let last_activity = events.iter().rev().find(|e| e.cwd.is_some());
+    match (format, point) {
+        (OutputFormat::Text, Some(point)) => print_resume(&point),
+        (OutputFormat::Text, None) => println!("No previous session found."),
+        (_, point) => print!("{}", utils::json_document(format, &point)?),
+    }
 
-    if let Some(event) = last_activity {
-        let cwd = event.cwd.as_ref().unwrap();
+    Ok(())
+}
 
-        println!("{}", "Last Active Session:".bold().cyan());
-        println!();
+pub fn resume_point(store: &dyn EventStore) -> Result<Option<ResumePoint>> {
+    let Some(event) = store.last(&EventFilter::all(), &|e| e.cwd.is_some())? else {
+        return Ok(None);
+    };
 
-        if let Some(ref proj) = event.project {
-            println!("  Project:      {}", proj.yellow());
-        }
+    // Only pauses and commands from the resumed event on matter, so neither lookup has
+    // to walk back through the history when there are none.
+    let since = EventFilter {
+        from: Some(event.timestamp),
+        ..EventFilter::default()
+    };
+
+    let last_pause = store.last(&since, &|e| {
+        matches!(e.event_type, EventType::Paused { .. })
+    })?;
+    let (paused_at, paused_until) = match last_pause {
+        Some(pause) => match pause.event_type {
+            EventType::Paused { until } => (Some(pause.timestamp), until),
+            _ => (None, None),
+        },
+        _ => (None, None),
+    };
+
+    let last_command = store
+        .last(&since, &|e| {
+            matches!(e.event_type, EventType::Command { .. })
+        })?
+        .and_then(|e| match e.event_type {
+            EventType::Command { cmd, .. } => Some(cmd),
+            _ => None,
+        });
+
+    Ok(Some(ResumePoint {
+        cwd: event.cwd.unwrap_or_default(),
+        project: event.project,
+        session_id: event.session_id,
+        timestamp: event.timestamp,
+        last_command,
+        paused_at,
+        paused_until,
+        tracking: None,
+    }))
+}
 
-        println!("  Path:         {}", cwd.blue());
+fn print_resume(point: &ResumePoint) {
+    println!("{}", "Last Active Session:".bold().cyan());
+    println!();
+
+    if let Some(ref proj) = point.project {
+        println!("  Project:      {}", proj.yellow());
+    }
+
+    println!("  Path:         {}", point.cwd.blue());
+    println!(
+        "  Time:         {}",
+        point
+            .timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+            .dimmed()
+    );
+
+    if let Some(paused_at) = point.paused_at {
         println!(
-            "  Time:         {}",
-            event
-                .timestamp
+            "  Paused:       {}{}",
+            paused_at
                 .with_timezone(&Local)
                 .format("%Y-%m-%d %H:%M:%S")
                 .to_string()
-                .dimmed()
+                .dimmed(),
+            until_label(point.paused_until).dimmed()
         );
-
-        let last_cmd = events
-            .iter()
-            .rev()
-            .find(|e| matches!(e.event_type, EventType::Command { .. }));
-
-        if let Some(cmd_event) = last_cmd {
-            if let EventType::Command { cmd } = &cmd_event.event_type {
-                println!("  Last command: {}", cmd.green());
-            }
-        }
-
-        println!();
-        println!("{}", cwd);
-    } else {
-        println!("No previous session found.");
+    }
+    if let Some(ref status) = point.tracking {
+        println!("  Tracking:     {}", status.yellow());
     }
 
-    Ok(())
+    if let Some(ref cmd) = point.last_command {
+        println!("  Last command: {}", cmd.green());
+    }
 }
 
-pub fn today() -> Result<()> {
-    let timeline_path = Config::timeline_path()?;
+pub fn today(args: OutputArgs) -> Result<()> {
+    let store = storage::open()?;
+    let format = args.format;
+    let date = Local::now().date_naive();
+
+    let today_events = store.scan(&EventFilter::on_date(date))?;
 
-    if !timeline_path.exists() {
+    if format == OutputFormat::Text && today_events.is_empty() {
         println!("No activity recorded today.");
         return Ok(());
     }
 
-    let contents = fs::read_to_string(&timeline_path)?;
-    let today = Local::now().date_naive();
-
-    let today_events: Vec<Event> = contents
-        .lines()
-        .filter_map(|line| serde_json::from_str::<Event>(line).ok())
-        .filter(|e| e.timestamp.with_timezone(&Local).date_naive() == today)
-        .collect();
+    let config = Config::load()?;
+    let mut summary = summarize_day(&today_events, date, config.idle_timeout_minutes, Utc::now());
+    summary.tracking = session::pause_status()?;
 
-    if today_events.is_empty() {
-        println!("No activity recorded today.");
-        return Ok(());
+    match format {
+        OutputFormat::Text => print_today(&summary),
+        _ => print!("{}", utils::json_document(format, &summary)?),
     }
 
-    println!("{}", "Today's Summary".bold().cyan());
-    println!();
+    Ok(())
+}
 
-    let mut projects = HashMap::new();
-    let mut commands = 0;
+pub fn summarize_day(
+    events: &[Event],
+    date: NaiveDate,
+    idle_timeout_minutes: u64,
+    now: DateTime<Utc>,
+) -> TodaySummary {
+    let active = activity::active_time(events, idle_timeout_minutes);
+    let timed = || events.iter().filter(|e| !e.has_synthesized_time());
 
-    for event in &today_events {
-        if matches!(event.event_type, EventType::Command { .. }) {
-            commands += 1;
-        }
+    let mut projects = HashMap::new();
+    for event in timed() {
         if let Some(ref proj) = event.project {
-            *projects.entry(proj.clone()).or_insert(0) += 1;
+            let time = active
+                .by_project
+                .get(proj)
+                .copied()
+                .unwrap_or_else(Duration::zero);
+            projects.insert(proj.clone(), time);
         }
     }
 
-    println!("  Events:   {}", today_events.len().to_string().yellow());
-    println!("  Commands: {}", commands.to_string().green());
-    println!("  Projects: {}", projects.len().to_string().cyan());
+    TodaySummary {
+        date,
+        events: timed().count(),
+        commands: timed()
+            .filter(|e| matches!(e.event_type, EventType::Command { .. }))
+            .count(),
+        active_secs: active.total.num_seconds(),
+        idle_secs: activity::total_idle(events).num_seconds(),
+        paused_secs: activity::total_paused(events, now).num_seconds(),
+        projects: project_times(&projects),
+        tracking: None,
+    }
+}
+
+fn print_today(summary: &TodaySummary) {
+    println!("{}", "Today's Summary".bold().cyan());
+    println!();
 
-    if !projects.is_empty() {
+    println!("  Events:   {}", summary.events.to_string().yellow());
+    println!("  Commands: {}", summary.commands.to_string().green());
+    println!("  Projects: {}", summary.projects.len().to_string().cyan());
+    println!("  Active:   {}", format_secs(summary.active_secs).yellow());
+
+    if summary.idle_secs > 0 {
+        println!("  Idle:     {}", format_secs(summary.idle_secs).dimmed());
+    }
+    if summary.paused_secs > 0 {
+        println!("  Paused:   {}", format_secs(summary.paused_secs).dimmed());
+    }
+    if let Some(ref status) = summary.tracking {
+        println!("  Tracking: {}", status.yellow());
+    }
+
+    if !summary.projects.is_empty() {
         println!();
         println!("{}", "  Active Projects:".bold());
-        for (proj, count) in projects.iter() {
-            println!("    {} ({} activities)", proj.yellow(), count);
+        for project in &summary.projects {
+            println!(
+                "    {} ({})",
+                project.project.yellow(),
+                format_secs(project.active_secs)
+            );
         }
     }
-
-    Ok(())
 }
 
-pub fn projects() -> Result<()> {
-    let timeline_path = Config::timeline_path()?;
+pub fn projects(args: OutputArgs) -> Result<()> {
+    let store = storage::open()?;
+    let format = args.format;
 
-    if !timeline_path.exists() {
+    if format == OutputFormat::Text && !store.exists() {
         println!("No activity history found.");
         return Ok(());
     }
 
-    let contents = fs::read_to_string(&timeline_path)?;
-    let events: Vec<Event> = contents
-        .lines()
-        .filter_map(|line| serde_json::from_str(line).ok())
-        .collect();
-
-    let mut project_stats: HashMap<String, (usize, String)> = HashMap::new();
+    let projects = project_activity(store.projects()?);
 
-    for event in &events {
-        if let Some(ref proj) = event.project {
-            let entry = project_stats
-                .entry(proj.clone())
-                .or_insert((0, String::new()));
-            entry.0 += 1;
-            if let Some(ref cwd) = event.cwd {
-                entry.1 = cwd.clone();
-            }
-        }
+    match format {
+        OutputFormat::Text => print_projects(&projects),
+        _ => print!("{}", utils::json_stream(format, &projects)?),
     }
 
+    Ok(())
+}
+
+pub fn project_activity(projects: BTreeMap<String, Summary>) -> Vec<ProjectActivity> {
+    let mut activity: Vec<ProjectActivity> = projects
+        .into_iter()
+        .map(|(project, summary)| ProjectActivity {
+            project,
+            events: summary.events,
+            first_seen: summary.first,
+            last_seen: summary.last,
+            last_cwd: summary.last_cwd,
+        })
+        .collect();
+    activity.sort_by_key(|project| std::cmp::Reverse(project.events));
+    activity
+}
+
+fn print_projects(projects: &[ProjectActivity]) {
     println!("{}", "Project Activity".bold().cyan());
     println!();
 
-    if project_stats.is_empty() {
+    if projects.is_empty() {
         println!("No projects tracked yet.");
 
         if let Some(config) = ProjWarp::load() {
@@ -476,16 +782,63 @@ pub fn projects() -> Result<()> {
                 println!("  {} -> {}", alias.yellow(), path.dimmed());
             }
         }
-    } else {
-        let mut projects: Vec<_> = project_stats.iter().collect();
-        projects.sort_by(|a, b| b.1.0.cmp(&a.1.0));
+        return;
+    }
 
-        for (proj, (count, path)) in projects {
-            println!("  {} ({} activities)", proj.yellow().bold(), count);
-            println!("    {}", path.dimmed());
-            println!();
-        }
+    for project in projects {
+        let path = project.last_cwd.as_deref().unwrap_or_default();
+        println!(
+            "  {} ({} activities)",
+            project.project.yellow().bold(),
+            project.events
+        );
+        println!("    {}", path.dimmed());
+        println!();
     }
+}
 
-    Ok(())
+fn project_times(times: &HashMap<String, Duration>) -> Vec<ProjectTime> {
+    let mut projects: Vec<ProjectTime> = times
+        .iter()
+        .map(|(project, time)| ProjectTime {
+            project: project.clone(),
+            active_secs: time.num_seconds(),
+        })
+        .collect();
+    projects.sort_by(|a, b| {
+        b.active_secs
+            .cmp(&a.active_secs)
+            .then_with(|| a.project.cmp(&b.project))
+    });
+    projects
+}
+
+fn format_secs(secs: i64) -> String {
+    utils::format_duration(Duration::seconds(secs))
+}
+
+fn until_label(until: Option<DateTime<Utc>>) -> String {
+    until
+        .map(|until| {
+            format!(
+                " until {}",
+                until.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            )
+        })
+        .unwrap_or_default()
+}
+
+fn command_outcome(exit_code: Option<i32>, duration_ms: Option<u64>) -> String {
+    let mut outcome = String::new();
+
+    if let Some(ms) = duration_ms {
+        let duration = utils::format_duration(Duration::milliseconds(ms as i64));
+        outcome.push_str(&format!(" {}", format!("({})", duration).dimmed()));
+    }
+
+    if let Some(code) = exit_code.filter(|&code| code != 0) {
+        outcome.push_str(&format!(" {}", format!("[exit {}]", code).red()));
+    }
+
+    outcome
 }
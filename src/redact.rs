@@ -0,0 +1,239 @@
+use crate::cli::RedactArgs;
+use crate::config::Config;
+use crate::events::{Event, EventType};
+use crate::pruner;
+use crate::segments::SegmentedStore;
+use crate::storage::JsonlStore;
+use anyhow::{Context, Result};
+use colored::*;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const MARKER: &str = "[REDACTED]";
+
+const MIN_ENTROPY_LEN: usize = 24;
+const MIN_ENTROPY_BITS: f64 = 4.0;
+
+const BUILTIN_RULES: &[&str] = &[
+    r"(?i)\b[a-z0-9_]*(?:secret|token|passw(?:or)?d|api_?key|access_?key|private_?key|credentials?)[a-z0-9_]*=(\x22[^\x22]*\x22|'[^']*'|\S+)",
+    r"(?i)\bauthorization:\s*(?:bearer|basic|token)\s+([^\s'\x22]+)",
+    r"(?i)\bbearer\s+([A-Za-z0-9._~+/=-]{8,})",
+    r"(?i)--(?:password|passwd|token|secret|api-key|access-key|auth-token)(?:=|\s+)([^\s'\x22]+)",
+    r"\b(?:mysql|mysqldump|mysqladmin|mariadb)\b[^|;&]*?\s-p([^\s'\x22]+)",
+    r"\bsshpass\s+-p\s*([^\s'\x22]+)",
+    r"(?i)\b[a-z][a-z0-9+.-]*://[^/\s:@]+:([^@\s/]+)@",
+    r"\b((?:AKIA|ASIA)[0-9A-Z]{16})\b",
+    r"\b((?:ghp|gho|ghu|ghs|ghr)_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})\b",
+    r"\b(xox[abprs]-[A-Za-z0-9-]{10,})",
+    r"\b(sk-[A-Za-z0-9_-]{20,})",
+    r"\b(AIza[0-9A-Za-z_-]{35})",
+    r"\b(eyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+)",
+];
+
+pub struct Redactor {
+    enabled: bool,
+    rules: Vec<Regex>,
+    token: Regex,
+}
+
+impl Redactor {
+    pub fn new(config: &Config) -> Result<Self> {
+        let mut rules = BUILTIN_RULES
+            .iter()
+            .map(|rule| Regex::new(rule).expect("built-in redaction rule"))
+            .collect::<Vec<_>>();
+
+        for rule in &config.redaction.rules {
+            rules.push(
+                Regex::new(rule).with_context(|| format!("Invalid redaction rule: {}", rule))?,
+            );
+        }
+
+        Ok(Self {
+            enabled: config.redaction.enabled,
+            rules,
+            // Word separators split candidates, so branch names, pod names and UUIDs are
+            // scored a piece at a time instead of as one long token.
+            token: Regex::new(r"[A-Za-z0-9+=]+").expect("token pattern"),
+        })
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        if !self.enabled {
+            return text.to_string();
+        }
+
+        let mut redacted = text.to_string();
+        for rule in &self.rules {
+            redacted = replace_secrets(rule, &redacted);
+        }
+
+        self.token
+            .replace_all(&redacted, |caps: &regex::Captures| {
+                let token = &caps[0];
+                if is_high_entropy(token) {
+                    MARKER.to_string()
+                } else {
+                    token.to_string()
+                }
+            })
+            .into_owned()
+    }
+
+    pub fn redact_event(&self, event: &mut Event) -> bool {
+        let text = match &mut event.event_type {
+            EventType::Command { cmd, .. } => cmd,
+            EventType::Note { text } => text,
+            _ => return false,
+        };
+
+        let redacted = self.redact(text);
+        if redacted == *text {
+            return false;
+        }
+
+        *text = redacted;
+        true
+    }
+}
+
+fn replace_secrets(rule: &Regex, text: &str) -> String {
+    rule.replace_all(text, |caps: &regex::Captures| {
+        let whole = caps.get(0).unwrap();
+        let secret = caps.get(1).unwrap_or(whole);
+
+        if secret.as_str() == MARKER {
+            return whole.as_str().to_string();
+        }
+
+        format!(
+            "{}{}{}",
+            &text[whole.start()..secret.start()],
+            MARKER,
+            &text[secret.end()..whole.end()]
+        )
+    })
+    .into_owned()
+}
+
+fn is_high_entropy(token: &str) -> bool {
+    if token.len() < MIN_ENTROPY_LEN || token.contains(MARKER) {
+        return false;
+    }
+
+    let has_digit = token.chars().any(|c| c.is_ascii_digit());
+    let has_alpha = token.chars().any(|c| c.is_ascii_alphabetic());
+    if !has_digit || !has_alpha {
+        return false;
+    }
+
+    // Hex runs are commit ids and digests far more often than secrets.
+    if token.chars().all(|c| c.is_ascii_hexdigit()) {
+        return false;
+    }
+
+    shannon_entropy(token) > MIN_ENTROPY_BITS
+}
+
+pub fn shannon_entropy(text: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in text.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+
+    let len = text.chars().count() as f64;
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+pub fn redact_history(args: RedactArgs) -> Result<()> {
+    let config = Config::load()?;
+    let redactor = Redactor::new(&config)?;
+
+    let store = SegmentedStore::open()?;
+    let _lock = store.lock()?;
+
+    let mut total = 0usize;
+
+    for segment in store.segment_paths()? {
+        let (lines, count) = redact_file(&redactor, &segment)?;
+        if count > 0 && !args.dry_run {
+            store.replace_lines(&segment, &lines)?;
+        }
+        report(&segment, count, args.dry_run);
+        total += count;
+    }
+
+    for archive in pruner::archive_paths(&Config::data_dir()?)? {
+        let (lines, count) = redact_file(&redactor, &archive)?;
+        if count > 0 && !args.dry_run {
+            JsonlStore::new(&archive).replace_lines(&lines)?;
+        }
+        report(&archive, count, args.dry_run);
+        total += count;
+    }
+
+    if total == 0 {
+        println!("No secrets found in history.");
+    } else if args.dry_run {
+        println!(
+            "{} {} events would be redacted.",
+            "[dry-run]".yellow(),
+            total.to_string().yellow()
+        );
+    } else {
+        println!("Redacted {} events.", total.to_string().yellow());
+    }
+
+    Ok(())
+}
+
+fn redact_file(redactor: &Redactor, path: &Path) -> Result<(Vec<String>, usize)> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut lines = Vec::new();
+    let mut count = 0usize;
+
+    for line in contents.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Ok(mut event) = serde_json::from_str::<Event>(line)
+            && redactor.redact_event(&mut event)
+        {
+            lines.push(serde_json::to_string(&event)?);
+            count += 1;
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+
+    Ok((lines, count))
+}
+
+fn report(path: &Path, count: usize, dry_run: bool) {
+    if count == 0 {
+        return;
+    }
+
+    let prefix = if dry_run {
+        format!("{} ", "[dry-run]".yellow())
+    } else {
+        String::new()
+    };
+    println!(
+        "{}{} events in {}",
+        prefix,
+        count.to_string().yellow(),
+        path.display().to_string().dimmed()
+    );
+}
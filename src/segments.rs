@@ -0,0 +1,624 @@
+use crate::config::Config;
+use crate::events::Event;
+use crate::lock::{self, FileLock};
+use crate::storage::{EventFilter, EventStore, IndexedStore, JsonlStore, Summary};
+use crate::utils::{self, AtomicWriter};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, ErrorKind, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+const MANIFEST_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// The UTC month an event is stored under, `YYYY-MM`.
+pub fn month(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y-%m").to_string()
+}
+
+fn is_month(name: &str) -> bool {
+    name.len() == 7 && NaiveDate::parse_from_str(&format!("{}-01", name), "%Y-%m-%d").is_ok()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn bound(timestamp: Option<DateTime<Utc>>) -> String {
+    timestamp.map_or_else(|| "none".to_string(), |t| t.to_rfc3339())
+}
+
+/// One month of the timeline as recorded in the manifest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    /// The UTC month, `YYYY-MM`.
+    pub month: String,
+    /// Events counted so far.
+    pub events: usize,
+    /// Earliest timestamp counted so far.
+    pub first: Option<DateTime<Utc>>,
+    /// Latest timestamp counted so far.
+    pub last: Option<DateTime<Utc>>,
+    /// Bytes of the segment file counted so far.
+    pub bytes: u64,
+}
+
+impl Segment {
+    fn new(month: String) -> Self {
+        Self {
+            month,
+            events: 0,
+            first: None,
+            last: None,
+            bytes: 0,
+        }
+    }
+
+    fn record(&mut self, event: &Event) {
+        self.events += 1;
+        if self.first.is_none_or(|first| event.timestamp < first) {
+            self.first = Some(event.timestamp);
+        }
+        if self.last.is_none_or(|last| event.timestamp > last) {
+            self.last = Some(event.timestamp);
+        }
+    }
+
+    fn overlaps(&self, filter: &EventFilter) -> bool {
+        match (self.first, self.last) {
+            (Some(first), Some(last)) => filter.overlaps(first, last),
+            _ => false,
+        }
+    }
+
+    // Counts the complete lines appended since the last count, up to byte `end`.
+    fn count(&mut self, path: &Path, end: u64) -> Result<()> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(self.bytes))?;
+        let mut reader = BufReader::new(file);
+        let mut buf = Vec::new();
+
+        loop {
+            buf.clear();
+            let n = reader.read_until(b'\n', &mut buf)? as u64;
+            if n == 0 || buf.last() != Some(&b'\n') || self.bytes + n > end {
+                break;
+            }
+
+            self.bytes += n;
+            if let Ok(event) = serde_json::from_slice::<Event>(&buf) {
+                self.record(&event);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `manifest.json` in the timeline directory: every segment, oldest month first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Format version; a manifest with another version is rebuilt.
+    pub version: u32,
+    /// One entry per segment file.
+    pub segments: Vec<Segment>,
+}
+
+impl Manifest {
+    fn empty() -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            segments: Vec::new(),
+        }
+    }
+}
+
+/// The timeline as one JSONL file per UTC month, each with its own block index, and a
+/// manifest of their time ranges so queries only open the months they need.
+pub struct SegmentedStore {
+    dir: PathBuf,
+    lock_path: PathBuf,
+    opened: Cell<usize>,
+}
+
+impl SegmentedStore {
+    /// Opens the segments in `dir`. A month's file is created by its first append.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let lock_path = lock::lock_path(&dir);
+        Self {
+            dir,
+            lock_path,
+            opened: Cell::new(0),
+        }
+    }
+
+    /// Opens [`Config::timeline_dir`], first moving any events left in the single-file
+    /// timeline at [`Config::timeline_path`] into it.
+    pub fn open() -> Result<Self> {
+        let store = Self::new(Config::timeline_dir()?);
+        let legacy = Config::timeline_path()?;
+        if legacy.exists() {
+            store.split_legacy(&legacy)?;
+        }
+        Ok(store)
+    }
+
+    /// The timeline directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Takes the exclusive write lock shared by every segment.
+    pub fn lock(&self) -> Result<FileLock> {
+        FileLock::exclusive(&self.lock_path)
+    }
+
+    fn read_lock(&self) -> Result<FileLock> {
+        FileLock::shared(&self.lock_path)
+    }
+
+    /// The file for `month`, `YYYY-MM.jsonl`, whether or not it exists yet.
+    pub fn segment_path(&self, month: &str) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", month))
+    }
+
+    /// The segment files on disk, oldest month first.
+    pub fn segment_paths(&self) -> Result<Vec<PathBuf>> {
+        Ok(self
+            .months()?
+            .iter()
+            .map(|month| self.segment_path(month))
+            .collect())
+    }
+
+    fn months(&self) -> Result<Vec<String>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut months = Vec::new();
+        for entry in entries {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if let Some(month) = name.strip_suffix(".jsonl")
+                && is_month(month)
+            {
+                months.push(month.to_string());
+            }
+        }
+
+        months.sort();
+        Ok(months)
+    }
+
+    fn segment(&self, month: &str) -> IndexedStore {
+        IndexedStore::new(self.segment_path(month)).with_lock_path(&self.lock_path)
+    }
+
+    /// How many segments queries on this store have read so far.
+    pub fn segments_read(&self) -> usize {
+        self.opened.get()
+    }
+
+    fn read_segment(&self, month: &str) -> IndexedStore {
+        self.opened.set(self.opened.get() + 1);
+        self.segment(month)
+    }
+
+    /// Returns the manifest, first counting whatever was appended since it was saved.
+    pub fn manifest(&self) -> Result<Manifest> {
+        let _lock = self.read_lock()?;
+        self.refresh_manifest()
+    }
+
+    /// [`Self::manifest`] for a caller already holding [`Self::lock`].
+    pub fn locked_manifest(&self) -> Result<Manifest> {
+        self.refresh_manifest()
+    }
+
+    fn refresh_manifest(&self) -> Result<Manifest> {
+        let saved = self.saved_manifest();
+        let mut known: BTreeMap<String, Segment> = saved
+            .segments
+            .iter()
+            .map(|segment| (segment.month.clone(), segment.clone()))
+            .collect();
+
+        let mut manifest = Manifest::empty();
+        for month in self.months()? {
+            let path = self.segment_path(&month);
+            let len = fs::metadata(&path)?.len();
+            let mut segment = known
+                .remove(&month)
+                .filter(|segment| segment.bytes <= len)
+                .unwrap_or_else(|| Segment::new(month));
+            if segment.bytes < len {
+                segment.count(&path, len)?;
+            }
+            manifest.segments.push(segment);
+        }
+
+        if manifest.segments != saved.segments {
+            let _ = self.save_manifest(&manifest);
+        }
+        Ok(manifest)
+    }
+
+    /// Compares the saved manifest with the segment files without refreshing it, and
+    /// describes every mismatch. Lines appended after a segment was last counted are not
+    /// a mismatch; the next query counts them.
+    pub fn check_manifest(&self) -> Result<Vec<String>> {
+        let _lock = self.read_lock()?;
+
+        let path = self.dir.join(MANIFEST_FILE);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let saved = match serde_json::from_str::<Manifest>(&contents) {
+            Ok(manifest) if manifest.version == MANIFEST_VERSION => manifest,
+            Ok(manifest) => {
+                return Ok(vec![format!(
+                    "{} has version {}, expected {}",
+                    MANIFEST_FILE, manifest.version, MANIFEST_VERSION
+                )]);
+            }
+            Err(e) => return Ok(vec![format!("{} cannot be parsed: {}", MANIFEST_FILE, e)]),
+        };
+
+        let mut problems = Vec::new();
+        for segment in &saved.segments {
+            let path = self.segment_path(&segment.month);
+            let len = match fs::metadata(&path) {
+                Ok(meta) => meta.len(),
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    problems.push(format!(
+                        "{} lists {}, which does not exist",
+                        MANIFEST_FILE,
+                        file_name(&path)
+                    ));
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if segment.bytes > len {
+                problems.push(format!(
+                    "{} records {} bytes of {}, but it has {}",
+                    MANIFEST_FILE,
+                    segment.bytes,
+                    file_name(&path),
+                    len
+                ));
+                continue;
+            }
+
+            let mut counted = Segment::new(segment.month.clone());
+            counted.count(&path, segment.bytes)?;
+            if counted != *segment {
+                problems.push(format!(
+                    "{} records {} events from {} to {} in {}, but it holds {} from {} to {}",
+                    MANIFEST_FILE,
+                    segment.events,
+                    bound(segment.first),
+                    bound(segment.last),
+                    file_name(&path),
+                    counted.events,
+                    bound(counted.first),
+                    bound(counted.last)
+                ));
+            }
+        }
+
+        let listed: Vec<&str> = saved.segments.iter().map(|s| s.month.as_str()).collect();
+        for month in self.months()? {
+            if !listed.contains(&month.as_str()) {
+                problems.push(format!(
+                    "{} is not listed in {}",
+                    file_name(&self.segment_path(&month)),
+                    MANIFEST_FILE
+                ));
+            }
+        }
+
+        Ok(problems)
+    }
+
+    /// Index files in the timeline directory that the next query would throw away,
+    /// including those left behind by a segment that no longer exists.
+    pub fn stale_indexes(&self) -> Result<Vec<PathBuf>> {
+        let _lock = self.read_lock()?;
+        self.find_stale_indexes()
+    }
+
+    fn find_stale_indexes(&self) -> Result<Vec<PathBuf>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut stale = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "idx")
+                && IndexedStore::new(path.with_extension("jsonl")).has_stale_index()
+            {
+                stale.push(path);
+            }
+        }
+
+        stale.sort();
+        Ok(stale)
+    }
+
+    /// Recounts every segment from scratch, replaces the manifest, and deletes stale
+    /// indexes so the next query rebuilds them.
+    pub fn rebuild_manifest(&self) -> Result<Manifest> {
+        let _lock = self.lock()?;
+        let mut manifest = Manifest::empty();
+        if !self.dir.exists() {
+            return Ok(manifest);
+        }
+
+        for month in self.months()? {
+            let path = self.segment_path(&month);
+            let mut segment = Segment::new(month);
+            segment.count(&path, fs::metadata(&path)?.len())?;
+            manifest.segments.push(segment);
+        }
+        self.save_manifest(&manifest)?;
+
+        for path in self.find_stale_indexes()? {
+            fs::remove_file(path)?;
+        }
+        Ok(manifest)
+    }
+
+    fn saved_manifest(&self) -> Manifest {
+        fs::read_to_string(self.dir.join(MANIFEST_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Manifest>(&contents).ok())
+            .filter(|manifest| manifest.version == MANIFEST_VERSION)
+            .unwrap_or_else(Manifest::empty)
+    }
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<()> {
+        utils::write_atomic(
+            &self.dir.join(MANIFEST_FILE),
+            serde_json::to_string_pretty(manifest)?.as_bytes(),
+        )
+    }
+
+    fn overlapping(&self, filter: &EventFilter) -> Result<Vec<Segment>> {
+        Ok(self
+            .manifest()?
+            .segments
+            .into_iter()
+            .filter(|segment| segment.overlaps(filter))
+            .collect())
+    }
+
+    /// Drops the index and manifest entry of a segment after it was rewritten in place.
+    /// Paths outside the timeline directory are ignored. Call while holding [`Self::lock`].
+    pub fn invalidate(&self, path: &Path) -> Result<()> {
+        let Some(month) = self.month_of(path) else {
+            return Ok(());
+        };
+        self.segment(&month).invalidate_index()?;
+
+        let mut manifest = self.saved_manifest();
+        let before = manifest.segments.len();
+        manifest.segments.retain(|segment| segment.month != month);
+        if manifest.segments.len() != before {
+            self.save_manifest(&manifest)?;
+        }
+        Ok(())
+    }
+
+    fn month_of(&self, path: &Path) -> Option<String> {
+        if path.parent() != Some(self.dir.as_path()) {
+            return None;
+        }
+        let name = path.file_name()?.to_string_lossy();
+        name.strip_suffix(".jsonl")
+            .filter(|month| is_month(month))
+            .map(str::to_string)
+    }
+
+    /// Rewrites the segment at `path`. Call while holding [`Self::lock`].
+    pub fn replace_lines(&self, path: &Path, lines: &[String]) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        JsonlStore::new(path).replace_lines(lines)?;
+        self.invalidate(path)
+    }
+
+    /// Deletes a month of the timeline. Call while holding [`Self::lock`].
+    pub fn remove_segment(&self, month: &str) -> Result<()> {
+        let path = self.segment_path(month);
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.invalidate(&path)
+    }
+
+    /// Moves every line of a single-file timeline into the segment for its month, then
+    /// deletes the file. Unparseable lines go with the event before them. Returns the
+    /// number of lines moved.
+    pub fn split_legacy(&self, legacy: &Path) -> Result<usize> {
+        let _lock = self.lock()?;
+        if !legacy.exists() {
+            return Ok(0);
+        }
+        fs::create_dir_all(&self.dir)?;
+
+        let mut writers: BTreeMap<String, AtomicWriter> = BTreeMap::new();
+        let mut current: Option<String> = None;
+        let mut orphans: Vec<String> = Vec::new();
+        let mut moved = 0usize;
+
+        let file =
+            File::open(legacy).with_context(|| format!("Failed to read {}", legacy.display()))?;
+        let mut reader = BufReader::new(file);
+        let mut buf = Vec::new();
+
+        loop {
+            buf.clear();
+            if reader.read_until(b'\n', &mut buf)? == 0 {
+                break;
+            }
+            let line = String::from_utf8_lossy(&buf)
+                .trim_end_matches(['\n', '\r'])
+                .to_string();
+            if line.trim().is_empty() {
+                continue;
+            }
+            moved += 1;
+
+            if let Ok(event) = serde_json::from_str::<Event>(&line) {
+                current = Some(month(event.timestamp));
+            }
+            let Some(ref month) = current else {
+                orphans.push(line);
+                continue;
+            };
+
+            let writer = self.writer(&mut writers, month)?;
+            for orphan in orphans.drain(..) {
+                writer.write_line(&orphan)?;
+            }
+            writer.write_line(&line)?;
+        }
+
+        if !orphans.is_empty() {
+            let writer = self.writer(&mut writers, &month(Utc::now()))?;
+            for orphan in &orphans {
+                writer.write_line(orphan)?;
+            }
+        }
+
+        let months: Vec<String> = writers.keys().cloned().collect();
+        for writer in writers.into_values() {
+            writer.commit()?;
+        }
+        for month in &months {
+            self.invalidate(&self.segment_path(month))?;
+        }
+
+        fs::remove_file(legacy)?;
+        IndexedStore::new(legacy).invalidate_index()?;
+
+        Ok(moved)
+    }
+
+    // A writer for `month` that starts with the lines already in its segment.
+    fn writer<'a>(
+        &self,
+        writers: &'a mut BTreeMap<String, AtomicWriter>,
+        month: &str,
+    ) -> Result<&'a mut AtomicWriter> {
+        match writers.entry(month.to_string()) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let path = self.segment_path(month);
+                let mut writer = AtomicWriter::create(&path)?;
+                if path.exists() {
+                    for line in fs::read_to_string(&path)?.lines() {
+                        if !line.trim().is_empty() {
+                            writer.write_line(line)?;
+                        }
+                    }
+                }
+                Ok(entry.insert(writer))
+            }
+        }
+    }
+
+    fn summaries(
+        &self,
+        per_segment: impl Fn(&IndexedStore) -> Result<BTreeMap<String, Summary>>,
+    ) -> Result<BTreeMap<String, Summary>> {
+        let mut totals: BTreeMap<String, Summary> = BTreeMap::new();
+        for month in self.months()? {
+            for (key, summary) in per_segment(&self.segment(&month))? {
+                totals.entry(key).or_default().merge(summary);
+            }
+        }
+        Ok(totals)
+    }
+}
+
+impl EventStore for SegmentedStore {
+    fn exists(&self) -> bool {
+        self.months().is_ok_and(|months| !months.is_empty())
+    }
+
+    fn append(&self, event: &Event) -> Result<()> {
+        self.append_all(std::slice::from_ref(event))
+    }
+
+    fn append_all(&self, events: &[Event]) -> Result<()> {
+        let mut by_month: BTreeMap<String, Vec<Event>> = BTreeMap::new();
+        for event in events {
+            by_month
+                .entry(month(event.timestamp))
+                .or_default()
+                .push(event.clone());
+        }
+
+        for (month, events) in by_month {
+            self.segment(&month).append_all(&events)?;
+        }
+        Ok(())
+    }
+
+    fn for_each(&self, filter: &EventFilter, f: &mut dyn FnMut(Event)) -> Result<()> {
+        for segment in self.overlapping(filter)? {
+            self.read_segment(&segment.month).for_each(filter, f)?;
+        }
+        Ok(())
+    }
+
+    // Months do not overlap, so the newest segment with a match holds the answer.
+    fn last(&self, filter: &EventFilter, pred: &dyn Fn(&Event) -> bool) -> Result<Option<Event>> {
+        for segment in self.overlapping(filter)?.iter().rev() {
+            if let Some(event) = self.read_segment(&segment.month).last(filter, pred)? {
+                return Ok(Some(event));
+            }
+        }
+        Ok(None)
+    }
+
+    fn recent(&self, filter: &EventFilter, limit: usize) -> Result<Vec<Event>> {
+        let mut events = Vec::new();
+        for segment in self.overlapping(filter)?.iter().rev() {
+            if events.len() >= limit {
+                break;
+            }
+            events.extend(
+                self.read_segment(&segment.month)
+                    .recent(filter, limit - events.len())?,
+            );
+        }
+        Ok(events)
+    }
+
+    fn projects(&self) -> Result<BTreeMap<String, Summary>> {
+        self.summaries(|segment| segment.projects())
+    }
+
+    fn sessions(&self) -> Result<BTreeMap<String, Summary>> {
+        self.summaries(|segment| segment.sessions())
+    }
+}
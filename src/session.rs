@@ -1,68 +1,369 @@
+use crate::activity;
+use crate::cli::{OutputArgs, OutputFormat, PauseArgs, UnpauseArgs};
 use crate::config::Config;
-use crate::events::Event;
-use anyhow::{anyhow, Result};
-use chrono::{DateTime, Local, Utc};
+use crate::daemon;
+use crate::events::{Event, EventType};
+use crate::lock::{self, FileLock};
+use crate::logger::Logged;
+use crate::storage::{self, EventFilter, Summary};
+use crate::utils;
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Duration, Local, Utc};
 use colored::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
+use std::path::PathBuf;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
+const STALE_SESSION_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SessionState {
-    pub current_session_id: String,
+    #[serde(default)]
+    pub current_session_id: Option<String>,
+    #[serde(default)]
+    pub sessions: BTreeMap<String, SessionInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused: Option<Pause>,
+    #[serde(default, skip_serializing)]
+    session_start: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing)]
+    last_activity: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
     pub session_start: DateTime<Utc>,
     pub last_activity: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused: Option<Pause>,
+}
+
+impl SessionInfo {
+    fn new(session_start: DateTime<Utc>, last_activity: DateTime<Utc>) -> Self {
+        Self {
+            session_start,
+            last_activity,
+            last_cwd: None,
+            last_project: None,
+            paused: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pause {
+    pub since: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+}
+
+impl Pause {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.until.is_none_or(|until| now < until)
+    }
+}
+
+/// One update to the session state, recorded so it can be replayed on a fresh copy of
+/// `state.json`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionChange {
+    Start(String, DateTime<Utc>),
+    Touch(String, DateTime<Utc>),
+    Locate(String, String, Option<String>),
+    End(String),
+}
+
+impl SessionState {
+    pub fn apply(&mut self, change: &SessionChange) {
+        match change {
+            SessionChange::Start(session_id, at) => self.start(session_id.clone(), *at),
+            SessionChange::Touch(session_id, at) => self.touch(session_id, *at),
+            SessionChange::Locate(session_id, cwd, project) => {
+                self.locate(session_id, cwd.clone(), project.clone())
+            }
+            SessionChange::End(session_id) => self.end(session_id),
+        }
+    }
+
+    pub fn start(&mut self, session_id: String, now: DateTime<Utc>) {
+        self.sessions
+            .insert(session_id.clone(), SessionInfo::new(now, now));
+        self.current_session_id = Some(session_id);
+        self.prune_stale(now);
+    }
+
+    pub fn touch(&mut self, session_id: &str, now: DateTime<Utc>) {
+        self.sessions
+            .entry(session_id.to_string())
+            .and_modify(|info| info.last_activity = now)
+            .or_insert(SessionInfo::new(now, now));
+        self.prune_stale(now);
+    }
+
+    pub fn locate(&mut self, session_id: &str, cwd: String, project: Option<String>) {
+        if let Some(info) = self.sessions.get_mut(session_id) {
+            info.last_cwd = Some(cwd);
+            info.last_project = project;
+        }
+    }
+
+    pub fn pause(&mut self, terminal: Option<&str>, pause: Pause) {
+        match terminal {
+            Some(session_id) => {
+                let since = pause.since;
+                self.sessions
+                    .entry(session_id.to_string())
+                    .or_insert(SessionInfo::new(since, since))
+                    .paused = Some(pause);
+            }
+            None => self.paused = Some(pause),
+        }
+    }
+
+    pub fn unpause(&mut self, session_id: Option<&str>, now: DateTime<Utc>) -> Vec<Pause> {
+        let terminal = session_id
+            .and_then(|id| self.sessions.get_mut(id))
+            .and_then(|info| info.paused.take());
+
+        self.paused
+            .take()
+            .into_iter()
+            .chain(terminal)
+            .filter(|pause| pause.is_active(now))
+            .collect()
+    }
+
+    pub fn active_pause(&self, session_id: &str, now: DateTime<Utc>) -> Option<&Pause> {
+        self.sessions
+            .get(session_id)
+            .and_then(|info| info.paused.as_ref())
+            .into_iter()
+            .chain(self.paused.as_ref())
+            .find(|pause| pause.is_active(now))
+    }
+
+    pub fn active_pauses(&self, now: DateTime<Utc>) -> Vec<(Option<&str>, &Pause)> {
+        let global = self.paused.as_ref().map(|pause| (None, pause));
+        let terminals = self
+            .sessions
+            .iter()
+            .filter_map(|(id, info)| info.paused.as_ref().map(|pause| (Some(id.as_str()), pause)));
+
+        global
+            .into_iter()
+            .chain(terminals)
+            .filter(|(_, pause)| pause.is_active(now))
+            .collect()
+    }
+
+    pub fn end(&mut self, session_id: &str) {
+        self.sessions.remove(session_id);
+
+        if self.current_session_id.as_deref() == Some(session_id) {
+            self.current_session_id = self.most_recent();
+        }
+    }
+
+    pub fn rebuild(events: &[Event], now: DateTime<Utc>) -> Self {
+        let mut ordered: Vec<&Event> = events.iter().collect();
+        ordered.sort_by_key(|event| event.timestamp);
+
+        let mut state = Self::default();
+        for event in ordered {
+            let Some(ref session_id) = event.session_id else {
+                continue;
+            };
+
+            match event.event_type {
+                EventType::SessionStart => state.start(session_id.clone(), event.timestamp),
+                EventType::SessionEnd => state.end(session_id),
+                _ => {
+                    state.touch(session_id, event.timestamp);
+                    if let Some(ref cwd) = event.cwd {
+                        state.locate(session_id, cwd.clone(), event.project.clone());
+                    }
+                }
+            }
+        }
+
+        state.current_session_id = state.most_recent();
+        state.prune_stale(now);
+        state
+    }
+
+    pub fn orphans(&self, known: &BTreeSet<String>) -> Vec<String> {
+        self.sessions
+            .iter()
+            .filter(|(id, info)| !known.contains(*id) && info.paused.is_none())
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    pub fn has_dangling_current(&self) -> bool {
+        self.current_session_id
+            .as_ref()
+            .is_some_and(|id| !self.sessions.contains_key(id))
+    }
+
+    pub fn repair(&mut self, known: &BTreeSet<String>) -> Vec<String> {
+        let orphans = self.orphans(known);
+        for id in &orphans {
+            self.end(id);
+        }
+        if self.has_dangling_current() {
+            self.current_session_id = self.most_recent();
+        }
+        orphans
+    }
+
+    fn most_recent(&self) -> Option<String> {
+        self.sessions
+            .iter()
+            .max_by_key(|(_, info)| info.last_activity)
+            .map(|(id, _)| id.clone())
+    }
+
+    fn prune_stale(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - Duration::days(STALE_SESSION_DAYS);
+        let current = self.current_session_id.clone();
+        self.sessions
+            .retain(|id, info| info.last_activity >= cutoff || Some(id) == current.as_ref());
+
+        if self
+            .paused
+            .as_ref()
+            .is_some_and(|pause| !pause.is_active(now))
+        {
+            self.paused = None;
+        }
+        for info in self.sessions.values_mut() {
+            if info
+                .paused
+                .as_ref()
+                .is_some_and(|pause| !pause.is_active(now))
+            {
+                info.paused = None;
+            }
+        }
+    }
+
+    fn migrate_legacy(&mut self) {
+        if let (Some(id), Some(start), Some(last)) = (
+            self.current_session_id.as_ref(),
+            self.session_start.take(),
+            self.last_activity.take(),
+        ) {
+            self.sessions
+                .entry(id.clone())
+                .or_insert(SessionInfo::new(start, last));
+        }
+    }
 }
 
 pub struct SessionManager;
 
 impl SessionManager {
     pub fn current_session_id() -> Result<String> {
-        let state_path = Config::state_path()?;
+        Self::load_state()?.current_session_id.ok_or_else(|| {
+            anyhow!("No active session. Start a session with: trail log --session-start")
+        })
+    }
 
-        if state_path.exists() {
-            let contents = fs::read_to_string(&state_path)?;
-            let state: SessionState = serde_json::from_str(&contents)?;
-            Ok(state.current_session_id)
-        } else {
-            Err(anyhow!(
-                "No active session. Start a session with: trail log --session-start"
-            ))
+    pub fn resolve(requested: Option<String>) -> Result<String> {
+        match requested.filter(|id| !id.is_empty()) {
+            Some(id) => Ok(id),
+            None => match Self::load_state()?.current_session_id {
+                Some(id) => Ok(id),
+                None => Self::new_session(),
+            },
         }
     }
 
-    pub fn current_session_id_or_create() -> Result<String> {
-        match Self::current_session_id() {
-            Ok(id) => Ok(id),
-            Err(_) => Self::new_session(),
-        }
+    pub fn pause(terminal: Option<&str>, pause: Pause) -> Result<()> {
+        Self::update_state(|state| state.pause(terminal, pause))
     }
 
-    pub fn update_last_activity() -> Result<()> {
-        let state_path = Config::state_path()?;
+    pub fn unpause(session_id: Option<&str>) -> Result<Vec<Pause>> {
+        Self::update_state(|state| state.unpause(session_id, Utc::now()))
+    }
 
-        if state_path.exists() {
-            let contents = fs::read_to_string(&state_path)?;
-            let mut state: SessionState = serde_json::from_str(&contents)?;
-            state.last_activity = Utc::now();
-            Self::save_state(&state)?;
-        }
+    pub fn active_pause(session_id: &str) -> Result<Option<Pause>> {
+        Ok(Self::load_state()?
+            .active_pause(session_id, Utc::now())
+            .cloned())
+    }
+
+    pub fn active_pauses() -> Result<Vec<(Option<String>, Pause)>> {
+        Ok(Self::load_state()?
+            .active_pauses(Utc::now())
+            .into_iter()
+            .map(|(id, pause)| (id.map(str::to_string), pause.clone()))
+            .collect())
+    }
 
-        Ok(())
+    pub fn state() -> Result<SessionState> {
+        Self::load_state()
+    }
+
+    /// Replays `changes` on the current `state.json` under its lock and returns the result.
+    pub fn apply(changes: &[SessionChange]) -> Result<SessionState> {
+        Self::update_state(|state| {
+            for change in changes {
+                state.apply(change);
+            }
+            state.clone()
+        })
+    }
+
+    /// Resolves one logged event against the current `state.json` and applies its changes
+    /// under the state lock, so shells logging at once never resolve against stale state.
+    /// `resolve` writes the events too; the state is only saved once it succeeds.
+    pub fn record(resolve: impl FnOnce(&SessionState) -> Result<Logged>) -> Result<Logged> {
+        Self::try_update_state(|state| {
+            let logged = resolve(state)?;
+            for change in &logged.changes {
+                state.apply(change);
+            }
+            Ok(logged)
+        })
     }
 
     pub fn new_session() -> Result<String> {
         let session_id = Self::generate_session_id();
-        let state = SessionState {
-            current_session_id: session_id.clone(),
-            session_start: Utc::now(),
-            last_activity: Utc::now(),
-        };
-        Self::save_state(&state)?;
+        Self::update_state(|state| state.start(session_id.clone(), Utc::now()))?;
         Ok(session_id)
     }
 
-    fn generate_session_id() -> String {
+    pub fn open_sessions() -> Result<BTreeMap<String, SessionInfo>> {
+        Ok(Self::load_state()?.sessions)
+    }
+
+    pub fn repair(known: &BTreeSet<String>) -> Result<Vec<String>> {
+        Self::update_state(|state| state.repair(known))
+    }
+
+    pub fn rebuild(events: &[Event]) -> Result<PathBuf> {
+        let state_path = Config::state_path()?;
+        let backup = state_path.with_extension("json.bak");
+
+        let _lock = FileLock::exclusive(&lock::lock_path(&state_path))?;
+        if state_path.exists() {
+            fs::copy(&state_path, &backup)?;
+        }
+        Self::save_state(&SessionState::rebuild(events, Utc::now()))?;
+
+        Ok(backup)
+    }
+
+    pub fn generate_session_id() -> String {
         Uuid::new_v4().to_string()
     }
 
@@ -71,6 +372,40 @@ impl SessionManager {
         Self::generate_session_id()
     }
 
+    fn load_state() -> Result<SessionState> {
+        let state_path = Config::state_path()?;
+
+        if !state_path.exists() {
+            return Ok(SessionState::default());
+        }
+
+        let contents = fs::read_to_string(&state_path)?;
+        Self::parse_state(&contents).with_context(|| {
+            format!(
+                "Failed to parse {}. Run `trail doctor --fix` to rebuild it",
+                state_path.display()
+            )
+        })
+    }
+
+    pub fn parse_state(contents: &str) -> Result<SessionState> {
+        let mut state: SessionState = serde_json::from_str(contents)?;
+        state.migrate_legacy();
+        Ok(state)
+    }
+
+    fn update_state<R>(f: impl FnOnce(&mut SessionState) -> R) -> Result<R> {
+        Self::try_update_state(|state| Ok(f(state)))
+    }
+
+    fn try_update_state<R>(f: impl FnOnce(&mut SessionState) -> Result<R>) -> Result<R> {
+        let _lock = FileLock::exclusive(&lock::lock_path(&Config::state_path()?))?;
+        let mut state = Self::load_state()?;
+        let result = f(&mut state)?;
+        Self::save_state(&state)?;
+        Ok(result)
+    }
+
     fn save_state(state: &SessionState) -> Result<()> {
         let state_path = Config::state_path()?;
 
@@ -79,63 +414,228 @@ impl SessionManager {
         }
 
         let contents = serde_json::to_string_pretty(state)?;
-        fs::write(&state_path, contents)?;
-
-        Ok(())
+        utils::write_atomic(&state_path, contents.as_bytes())
     }
 }
 
-pub fn list_sessions() -> Result<()> {
-    let timeline_path = Config::timeline_path()?;
+#[derive(Debug, Serialize)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub events: usize,
+    /// Active time, with idle gaps and pauses left out.
+    pub duration_secs: i64,
+    /// Wall-clock time from the first event to the last.
+    pub elapsed_secs: i64,
+    pub overlaps: usize,
+    pub active: bool,
+}
 
-    if !timeline_path.exists() {
+pub fn list_sessions(args: OutputArgs) -> Result<()> {
+    let store = storage::open()?;
+    let format = args.format;
+
+    if format == OutputFormat::Text && !store.exists() {
         println!("No sessions recorded yet.");
         return Ok(());
     }
 
-    let contents = fs::read_to_string(&timeline_path)?;
-    let events: Vec<Event> = contents
-        .lines()
-        .filter_map(|line| serde_json::from_str(line).ok())
-        .collect();
+    let open_sessions = SessionManager::open_sessions()?;
+    let config = Config::load()?;
+    let active = activity::active_time(
+        &store.scan(&EventFilter::all())?,
+        config.idle_timeout_minutes,
+    );
+    let sessions = summarize_sessions(store.sessions()?, &active.by_session, &open_sessions);
 
-    use std::collections::HashMap;
-    let mut sessions: HashMap<String, Vec<&Event>> = HashMap::new();
-
-    for event in &events {
-        if let Some(ref session_id) = event.session_id {
-            sessions.entry(session_id.clone()).or_default().push(event);
-        }
+    match format {
+        OutputFormat::Text => print_sessions(&sessions),
+        _ => print!("{}", utils::json_stream(format, &sessions)?),
     }
 
-    let mut session_list: Vec<(String, Vec<&Event>)> = sessions.into_iter().collect();
-    session_list.sort_by_key(|(_, events)| {
-        events
-            .first()
-            .map(|e| e.timestamp)
-            .unwrap_or(DateTime::<Utc>::MIN_UTC)
-    });
-    session_list.reverse();
+    Ok(())
+}
+
+pub fn summarize_sessions(
+    sessions: BTreeMap<String, Summary>,
+    active: &HashMap<String, Duration>,
+    open_sessions: &BTreeMap<String, SessionInfo>,
+) -> Vec<SessionSummary> {
+    let mut spans: Vec<(String, usize, DateTime<Utc>, DateTime<Utc>)> = sessions
+        .into_iter()
+        .filter_map(|(id, summary)| Some((id, summary.events, summary.first?, summary.last?)))
+        .collect();
+    spans.sort_by_key(|(_, _, first, _)| std::cmp::Reverse(*first));
+
+    spans
+        .iter()
+        .map(|(session_id, events, start, end)| {
+            let overlaps = spans
+                .iter()
+                .filter(|(other_id, _, first, last)| {
+                    other_id != session_id && first <= end && last >= start
+                })
+                .count();
+
+            SessionSummary {
+                session_id: session_id.clone(),
+                start: *start,
+                end: *end,
+                events: *events,
+                duration_secs: active.get(session_id).map_or(0, |time| time.num_seconds()),
+                elapsed_secs: (*end - *start).num_seconds(),
+                overlaps,
+                active: open_sessions.contains_key(session_id),
+            }
+        })
+        .collect()
+}
 
+fn print_sessions(sessions: &[SessionSummary]) {
     println!("{}", "Sessions".bold().cyan());
     println!();
 
-    for (i, (_, events)) in session_list.iter().enumerate() {
-        let start = events.first().map(|e| e.timestamp).unwrap();
-        let end = events.last().map(|e| e.timestamp).unwrap();
-        let duration = end - start;
+    for (i, session) in sessions.iter().enumerate() {
+        let start_local = session.start.with_timezone(&Local);
+        let end_local = session.end.with_timezone(&Local);
+        let end_format = if start_local.date_naive() == end_local.date_naive() {
+            "%H:%M"
+        } else {
+            "%Y-%m-%d %H:%M"
+        };
 
-        let start_local = start.with_timezone(&Local);
-        let end_local = end.with_timezone(&Local);
+        let mut details = format!(
+            "{} events, {}",
+            session.events,
+            utils::format_duration(Duration::seconds(session.duration_secs))
+        );
+        if session.elapsed_secs > session.duration_secs {
+            details.push_str(&format!(
+                " ({} elapsed)",
+                utils::format_duration(Duration::seconds(session.elapsed_secs))
+            ));
+        }
+        if session.overlaps > 0 {
+            details.push_str(&format!(", overlaps {}", session.overlaps));
+        }
+
+        let status = if session.active {
+            format!(" {}", "active".green())
+        } else {
+            String::new()
+        };
 
         println!(
-            "  {} {}  {}  {}",
+            "  {} {}  {}  {}{}",
             format!("#{}", i + 1).dimmed(),
             start_local.format("%Y-%m-%d %H:%M").to_string().yellow(),
-            end_local.format("%H:%M").to_string().dimmed(),
-            format!("{} events, {}m", events.len(), duration.num_minutes()).dimmed()
+            end_local.format(end_format).to_string().dimmed(),
+            details.dimmed(),
+            status
         );
     }
+}
+
+pub fn pause_tracking(args: PauseArgs) -> Result<()> {
+    let now = Utc::now();
+    let until = args
+        .duration
+        .as_deref()
+        .map(utils::parse_duration)
+        .transpose()?
+        .map(|duration| now + duration);
+
+    let session_id = args.session.filter(|id| !id.is_empty());
+    if args.terminal && session_id.is_none() {
+        return Err(anyhow!(
+            "No terminal session to pause. Set OPSTRAIL_SESSION_ID or pass --session <id>"
+        ));
+    }
+
+    let marker_session = session_id
+        .clone()
+        .or_else(|| SessionManager::current_session_id().ok());
+    let pause = Pause {
+        since: now,
+        until,
+        session_id: marker_session.clone(),
+    };
+
+    let terminal = session_id.as_deref().filter(|_| args.terminal);
+    SessionManager::pause(terminal, pause)?;
+    daemon::notify_state_changed();
+
+    let mut marker = Event::new(EventType::Paused { until }).with_timestamp(now);
+    if let Some(id) = marker_session {
+        marker = marker.with_session(id);
+    }
+    storage::open()?.append(&marker)?;
+
+    let scope = if terminal.is_some() {
+        "this terminal"
+    } else {
+        "all terminals"
+    };
+    match until {
+        Some(until) => println!(
+            "Tracking paused for {} until {}.",
+            scope,
+            until
+                .with_timezone(&Local)
+                .format("%H:%M")
+                .to_string()
+                .yellow()
+        ),
+        None => println!(
+            "Tracking paused for {}. Run {} to resume.",
+            scope,
+            "trail unpause".yellow()
+        ),
+    }
 
     Ok(())
 }
+
+pub fn unpause_tracking(args: UnpauseArgs) -> Result<()> {
+    let session_id = args.session.filter(|id| !id.is_empty());
+    let cleared = SessionManager::unpause(session_id.as_deref())?;
+    daemon::notify_state_changed();
+
+    if cleared.is_empty() {
+        println!("Tracking is not paused.");
+        return Ok(());
+    }
+
+    let store = storage::open()?;
+    for pause in cleared {
+        let mut marker = Event::new(EventType::Unpaused);
+        if let Some(id) = pause.session_id {
+            marker = marker.with_session(id);
+        }
+        store.append(&marker)?;
+    }
+
+    println!("Tracking {}.", "resumed".green());
+
+    Ok(())
+}
+
+pub fn pause_status() -> Result<Option<String>> {
+    let pauses = SessionManager::active_pauses()?;
+    let Some((terminal, pause)) = pauses.first() else {
+        return Ok(None);
+    };
+
+    let scope = match (terminal, pauses.len()) {
+        (None, _) => "all terminals".to_string(),
+        (Some(_), 1) => "1 terminal".to_string(),
+        (Some(_), n) => format!("{} terminals", n),
+    };
+    let until = pause
+        .until
+        .map(|until| format!(" until {}", until.with_timezone(&Local).format("%H:%M")))
+        .unwrap_or_default();
+
+    Ok(Some(format!("paused for {}{}", scope, until)))
+}
@@ -0,0 +1,834 @@
+use crate::cli::FilterArgs;
+use crate::events::Event;
+use crate::lock::{self, FileLock};
+use crate::segments::SegmentedStore;
+use crate::utils;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+const INDEX_VERSION: u32 = 2;
+const BLOCK_LINES: usize = 256;
+const HEAD_BYTES: u64 = 128;
+const REVERSE_CHUNK: u64 = 64 * 1024;
+
+/// Read and append access to a timeline.
+pub trait EventStore {
+    /// Whether any events have been written yet.
+    fn exists(&self) -> bool;
+    /// Appends one event. The event is written as given, without redaction or ignore rules.
+    fn append(&self, event: &Event) -> Result<()>;
+    /// Appends events in order as one write.
+    fn append_all(&self, events: &[Event]) -> Result<()> {
+        for event in events {
+            self.append(event)?;
+        }
+        Ok(())
+    }
+    /// Calls `f` with each event matching `filter`, in file order, without collecting them.
+    fn for_each(&self, filter: &EventFilter, f: &mut dyn FnMut(Event)) -> Result<()>;
+
+    /// Returns the events matching `filter`, in file order.
+    fn scan(&self, filter: &EventFilter) -> Result<Vec<Event>> {
+        let mut events = Vec::new();
+        self.for_each(filter, &mut |event| events.push(event))?;
+        Ok(events)
+    }
+
+    /// Returns the latest event that matches both `filter` and `pred`.
+    fn last(&self, filter: &EventFilter, pred: &dyn Fn(&Event) -> bool) -> Result<Option<Event>> {
+        let mut best: Option<Event> = None;
+        self.for_each(filter, &mut |event| {
+            if pred(&event) && best.as_ref().is_none_or(|b| event.timestamp >= b.timestamp) {
+                best = Some(event);
+            }
+        })?;
+        Ok(best)
+    }
+
+    /// Returns up to `limit` events matching `filter`, newest first.
+    fn recent(&self, filter: &EventFilter, limit: usize) -> Result<Vec<Event>> {
+        let mut newest = Newest::new(limit, false);
+        self.for_each(filter, &mut |event| newest.push(event))?;
+        Ok(newest.into_vec())
+    }
+
+    /// Event counts and activity range per project.
+    fn projects(&self) -> Result<BTreeMap<String, Summary>>;
+    /// Event counts and activity range per session.
+    fn sessions(&self) -> Result<BTreeMap<String, Summary>>;
+}
+
+/// Opens the timeline in [`Config::timeline_dir`](crate::Config::timeline_dir).
+pub fn open() -> Result<Box<dyn EventStore>> {
+    Ok(Box::new(SegmentedStore::open()?))
+}
+
+/// Selects events by time range, project and session. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    /// Earliest timestamp, inclusive.
+    pub from: Option<DateTime<Utc>>,
+    /// Latest timestamp, inclusive.
+    pub to: Option<DateTime<Utc>>,
+    /// Only events in this project.
+    pub project: Option<String>,
+    /// Only events in this session.
+    pub session_id: Option<String>,
+}
+
+impl EventFilter {
+    /// Matches every event.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Events from `from` to `to`, inclusive.
+    pub fn between(from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        Self {
+            from: Some(from),
+            to: Some(to),
+            ..Self::default()
+        }
+    }
+
+    /// Events up to `to`, inclusive.
+    pub fn until(to: DateTime<Utc>) -> Self {
+        Self {
+            to: Some(to),
+            ..Self::default()
+        }
+    }
+
+    /// Events on one local calendar day.
+    pub fn on_date(date: NaiveDate) -> Self {
+        Self::dates(date, date)
+    }
+
+    /// Events from the start of local day `from` to the end of local day `to`.
+    pub fn dates(from: NaiveDate, to: NaiveDate) -> Self {
+        Self::between(utils::local_day_start(from), utils::local_day_end(to))
+    }
+
+    pub(crate) fn from_args(args: &FilterArgs) -> Result<Self> {
+        let mut filter = if args.today {
+            Self::on_date(Local::now().date_naive())
+        } else if let Some(ref date) = args.date {
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| anyhow!("Invalid --date format. Use YYYY-MM-DD"))?;
+            Self::on_date(date)
+        } else {
+            Self::all()
+        };
+
+        if let Some(ref from) = args.from {
+            filter.from = Some(utils::parse_time_bound(from, false)?);
+        }
+        if let Some(ref to) = args.to {
+            filter.to = Some(utils::parse_time_bound(to, true)?);
+        }
+        filter.project = args.project.clone();
+        filter.session_id = args.session.clone();
+
+        Ok(filter)
+    }
+
+    /// Whether no field is set.
+    pub fn is_unbounded(&self) -> bool {
+        self.from.is_none()
+            && self.to.is_none()
+            && self.project.is_none()
+            && self.session_id.is_none()
+    }
+
+    /// Also requires `project`.
+    pub fn with_project(mut self, project: String) -> Self {
+        self.project = Some(project);
+        self
+    }
+
+    /// Also requires `session_id`.
+    pub fn with_session(mut self, session_id: String) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// Whether `event` passes every set field.
+    pub fn matches(&self, event: &Event) -> bool {
+        if self.from.is_some_and(|from| event.timestamp < from) {
+            return false;
+        }
+        if self.to.is_some_and(|to| event.timestamp > to) {
+            return false;
+        }
+        if self.project.is_some() && event.project != self.project {
+            return false;
+        }
+        if self.session_id.is_some() && event.session_id != self.session_id {
+            return false;
+        }
+        true
+    }
+
+    /// Whether events between `min` and `max` could match the time range.
+    pub fn overlaps(&self, min: DateTime<Utc>, max: DateTime<Utc>) -> bool {
+        self.from.is_none_or(|from| max >= from) && self.to.is_none_or(|to| min <= to)
+    }
+}
+
+/// Totals for one project or session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Summary {
+    /// Number of events.
+    pub events: usize,
+    /// Earliest timestamp.
+    pub first: Option<DateTime<Utc>>,
+    /// Latest timestamp.
+    pub last: Option<DateTime<Utc>>,
+    /// Working directory of the last event that had one.
+    pub last_cwd: Option<String>,
+}
+
+impl Summary {
+    fn record(&mut self, event: &Event) {
+        self.events += 1;
+        if self.first.is_none_or(|first| event.timestamp < first) {
+            self.first = Some(event.timestamp);
+        }
+        if self.last.is_none_or(|last| event.timestamp > last) {
+            self.last = Some(event.timestamp);
+        }
+        if let Some(ref cwd) = event.cwd {
+            self.last_cwd = Some(cwd.clone());
+        }
+    }
+
+    /// Adds the totals from a later part of the timeline.
+    pub fn merge(&mut self, later: Summary) {
+        self.events += later.events;
+        self.first = self.first.into_iter().chain(later.first).min();
+        self.last = self.last.into_iter().chain(later.last).max();
+        if later.last_cwd.is_some() {
+            self.last_cwd = later.last_cwd;
+        }
+    }
+}
+
+pub struct JsonlStore {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl JsonlStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let lock_path = lock::lock_path(&path);
+        Self { path, lock_path }
+    }
+
+    /// Uses the lock at `lock_path` instead of one next to the file.
+    pub fn with_lock_path(mut self, lock_path: impl Into<PathBuf>) -> Self {
+        self.lock_path = lock_path.into();
+        self
+    }
+
+    pub fn lock(&self) -> Result<FileLock> {
+        FileLock::exclusive(&self.lock_path)
+    }
+
+    fn read_lock(&self) -> Result<FileLock> {
+        FileLock::shared(&self.lock_path)
+    }
+
+    pub fn replace_lines(&self, lines: &[String]) -> Result<()> {
+        let mut contents = String::new();
+        for line in lines {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+        utils::write_atomic(&self.path, contents.as_bytes())
+    }
+}
+
+impl EventStore for JsonlStore {
+    fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    fn append(&self, event: &Event) -> Result<()> {
+        self.append_all(std::slice::from_ref(event))
+    }
+
+    fn append_all(&self, events: &[Event]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut lines = String::new();
+        for event in events {
+            lines.push_str(&serde_json::to_string(event)?);
+            lines.push('\n');
+        }
+
+        let _lock = self.lock()?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(lines.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn for_each(&self, filter: &EventFilter, f: &mut dyn FnMut(Event)) -> Result<()> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+
+        let _lock = self.read_lock()?;
+        let reader = BufReader::new(File::open(&self.path)?);
+        let mut unparseable = 0usize;
+
+        for line in reader.lines() {
+            let line = line?;
+            match serde_json::from_str::<Event>(&line) {
+                Ok(event) if filter.matches(&event) => f(event),
+                Ok(_) => {}
+                Err(_) if line.trim().is_empty() => {}
+                Err(_) => unparseable += 1,
+            }
+        }
+
+        warn_unparseable(&self.path, unparseable);
+        Ok(())
+    }
+
+    fn projects(&self) -> Result<BTreeMap<String, Summary>> {
+        let mut projects: BTreeMap<String, Summary> = BTreeMap::new();
+        self.for_each(&EventFilter::all(), &mut |event| {
+            if let Some(ref project) = event.project {
+                projects.entry(project.clone()).or_default().record(&event);
+            }
+        })?;
+        Ok(projects)
+    }
+
+    fn sessions(&self) -> Result<BTreeMap<String, Summary>> {
+        let mut sessions: BTreeMap<String, Summary> = BTreeMap::new();
+        self.for_each(&EventFilter::all(), &mut |event| {
+            if let Some(ref session_id) = event.session_id {
+                sessions
+                    .entry(session_id.clone())
+                    .or_default()
+                    .record(&event);
+            }
+        })?;
+        Ok(sessions)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Block {
+    offset: u64,
+    len: u64,
+    lines: usize,
+    min: Option<DateTime<Utc>>,
+    max: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct IndexEntry {
+    blocks: Vec<usize>,
+    #[serde(flatten)]
+    summary: Summary,
+}
+
+impl IndexEntry {
+    fn record(&mut self, block: usize, event: &Event) {
+        if self.blocks.last() != Some(&block) {
+            self.blocks.push(block);
+        }
+        self.summary.record(event);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TimelineIndex {
+    version: u32,
+    indexed_bytes: u64,
+    head_len: u64,
+    head: String,
+    unparseable: usize,
+    blocks: Vec<Block>,
+    projects: BTreeMap<String, IndexEntry>,
+    sessions: BTreeMap<String, IndexEntry>,
+}
+
+impl TimelineIndex {
+    fn empty() -> Self {
+        Self {
+            version: INDEX_VERSION,
+            indexed_bytes: 0,
+            head_len: 0,
+            head: String::new(),
+            unparseable: 0,
+            blocks: Vec::new(),
+            projects: BTreeMap::new(),
+            sessions: BTreeMap::new(),
+        }
+    }
+
+    fn push_line(&mut self, offset: u64, len: u64, event: Option<&Event>) {
+        let start_new = match self.blocks.last() {
+            Some(block) => block.lines >= BLOCK_LINES || block.offset + block.len != offset,
+            None => true,
+        };
+        if start_new {
+            self.blocks.push(Block {
+                offset,
+                len: 0,
+                lines: 0,
+                min: None,
+                max: None,
+            });
+        }
+
+        let id = self.blocks.len() - 1;
+        let block = &mut self.blocks[id];
+        block.len += len;
+        block.lines += 1;
+
+        let Some(event) = event else {
+            return;
+        };
+
+        if block.min.is_none_or(|min| event.timestamp < min) {
+            block.min = Some(event.timestamp);
+        }
+        if block.max.is_none_or(|max| event.timestamp > max) {
+            block.max = Some(event.timestamp);
+        }
+        if let Some(ref project) = event.project {
+            self.projects
+                .entry(project.clone())
+                .or_default()
+                .record(id, event);
+        }
+        if let Some(ref session_id) = event.session_id {
+            self.sessions
+                .entry(session_id.clone())
+                .or_default()
+                .record(id, event);
+        }
+    }
+
+    fn candidate_blocks(&self, filter: &EventFilter) -> Vec<usize> {
+        let keyed = |map: &BTreeMap<String, IndexEntry>, key: &String| -> BTreeSet<usize> {
+            map.get(key)
+                .map(|entry| entry.blocks.iter().copied().collect())
+                .unwrap_or_default()
+        };
+
+        let mut ids: BTreeSet<usize> = match filter.project {
+            Some(ref project) => keyed(&self.projects, project),
+            None => (0..self.blocks.len()).collect(),
+        };
+        if let Some(ref session_id) = filter.session_id {
+            let session_blocks = keyed(&self.sessions, session_id);
+            ids.retain(|id| session_blocks.contains(id));
+        }
+
+        ids.into_iter()
+            .filter(|&id| match (self.blocks[id].min, self.blocks[id].max) {
+                (Some(min), Some(max)) => filter.overlaps(min, max),
+                _ => false,
+            })
+            .collect()
+    }
+}
+
+/// A JSONL timeline with a block index kept next to it. [`open`] keeps one per month.
+pub struct IndexedStore {
+    log: JsonlStore,
+    index_path: PathBuf,
+}
+
+impl IndexedStore {
+    /// Opens the timeline at `path`. The file is created on the first append.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let log = JsonlStore::new(path);
+        let index_path = log.path.with_extension("idx");
+        Self { log, index_path }
+    }
+
+    /// Uses the lock at `lock_path` instead of one next to the file.
+    pub fn with_lock_path(mut self, lock_path: impl Into<PathBuf>) -> Self {
+        self.log = self.log.with_lock_path(lock_path);
+        self
+    }
+
+    /// Takes the exclusive write lock shared with every `trail` process.
+    pub fn lock(&self) -> Result<FileLock> {
+        self.log.lock()
+    }
+
+    /// Rewrites the file with `lines` and drops its index.
+    pub fn replace_lines(&self, lines: &[String]) -> Result<()> {
+        self.log.replace_lines(lines)?;
+        self.invalidate_index()
+    }
+
+    /// Deletes the index so the next read rebuilds it.
+    pub fn invalidate_index(&self) -> Result<()> {
+        if self.index_path.exists() {
+            fs::remove_file(&self.index_path)?;
+        }
+        Ok(())
+    }
+
+    /// Whether an index file exists that would be thrown away on the next read, because
+    /// the timeline next to it is gone or no longer starts with the lines it indexed.
+    pub fn has_stale_index(&self) -> bool {
+        if !self.index_path.exists() {
+            return false;
+        }
+        match fs::metadata(&self.log.path) {
+            Ok(meta) => self.saved_index(meta.len()).is_none(),
+            Err(_) => true,
+        }
+    }
+
+    fn saved_index(&self, file_len: u64) -> Option<TimelineIndex> {
+        fs::read_to_string(&self.index_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<TimelineIndex>(&contents).ok())
+            .filter(|index| {
+                index.version == INDEX_VERSION
+                    && index.indexed_bytes <= file_len
+                    && read_head(&self.log.path, index.head_len)
+                        .is_ok_and(|head| head == index.head)
+            })
+    }
+
+    fn load_index(&self) -> Result<TimelineIndex> {
+        let file_len = match fs::metadata(&self.log.path) {
+            Ok(meta) => meta.len(),
+            Err(_) => return Ok(TimelineIndex::empty()),
+        };
+
+        let mut index = self
+            .saved_index(file_len)
+            .unwrap_or_else(TimelineIndex::empty);
+
+        if index.indexed_bytes < file_len {
+            self.extend_index(&mut index)?;
+            let _ = self.save_index(&index);
+        }
+
+        Ok(index)
+    }
+
+    fn extend_index(&self, index: &mut TimelineIndex) -> Result<()> {
+        let mut file = File::open(&self.log.path)?;
+        file.seek(SeekFrom::Start(index.indexed_bytes))?;
+        let mut reader = BufReader::new(file);
+
+        let mut offset = index.indexed_bytes;
+        let mut buf = Vec::new();
+
+        loop {
+            buf.clear();
+            let n = reader.read_until(b'\n', &mut buf)? as u64;
+            if n == 0 || buf.last() != Some(&b'\n') {
+                break;
+            }
+
+            let event = serde_json::from_slice::<Event>(&buf).ok();
+            if event.is_none() && !buf.trim_ascii().is_empty() {
+                index.unparseable += 1;
+            }
+            index.push_line(offset, n, event.as_ref());
+            offset += n;
+        }
+
+        index.indexed_bytes = offset;
+        if index.head_len < HEAD_BYTES {
+            index.head_len = HEAD_BYTES.min(offset);
+            index.head = read_head(&self.log.path, index.head_len)?;
+        }
+
+        Ok(())
+    }
+
+    fn save_index(&self, index: &TimelineIndex) -> Result<()> {
+        utils::write_atomic(&self.index_path, serde_json::to_string(index)?.as_bytes())
+    }
+
+    fn read_block(&self, file: &mut File, block: &Block) -> Result<Vec<Event>> {
+        let mut buf = vec![0u8; block.len as usize];
+        file.seek(SeekFrom::Start(block.offset))?;
+        file.read_exact(&mut buf)?;
+
+        Ok(buf
+            .split(|&b| b == b'\n')
+            .filter_map(|line| serde_json::from_slice::<Event>(line).ok())
+            .collect())
+    }
+
+    // Reads candidate blocks from the newest down, each from its last line back, and
+    // stops once no remaining block can hold anything newer than what was found.
+    fn newest(
+        &self,
+        filter: &EventFilter,
+        limit: usize,
+        pred: &dyn Fn(&Event) -> bool,
+    ) -> Result<Vec<Event>> {
+        let _lock = self.log.read_lock()?;
+        let index = self.load_index()?;
+        warn_unparseable(&self.log.path, index.unparseable);
+        let mut ids = index.candidate_blocks(filter);
+        if ids.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        ids.sort_by_key(|&id| Reverse((index.blocks[id].max, id)));
+
+        let mut file = File::open(&self.log.path)?;
+        let mut newest = Newest::new(limit, true);
+
+        for id in ids {
+            let block = &index.blocks[id];
+            if let (Some(oldest), Some(max)) = (newest.cutoff(), block.max)
+                && max < oldest
+            {
+                break;
+            }
+
+            let lines = ReverseLines::range(&mut file, block.offset, block.offset + block.len);
+            for line in lines {
+                if let Ok(event) = serde_json::from_slice::<Event>(&line?)
+                    && filter.matches(&event)
+                    && pred(&event)
+                {
+                    newest.push(event);
+                }
+            }
+        }
+
+        Ok(newest.into_vec())
+    }
+}
+
+// The `limit` newest events pushed so far. On equal timestamps the event later in the
+// file comes first, so `reversed` says whether events arrive from the end of the file.
+struct Newest {
+    limit: usize,
+    reversed: bool,
+    events: Vec<(DateTime<Utc>, i64, Event)>,
+    seen: i64,
+}
+
+impl Newest {
+    fn new(limit: usize, reversed: bool) -> Self {
+        Self {
+            limit,
+            reversed,
+            events: Vec::new(),
+            seen: 0,
+        }
+    }
+
+    fn push(&mut self, event: Event) {
+        if self.limit == 0 {
+            return;
+        }
+        self.seen += 1;
+        let position = if self.reversed { -self.seen } else { self.seen };
+        self.events.push((event.timestamp, position, event));
+        if self.events.len() >= self.limit * 2 {
+            self.trim();
+        }
+    }
+
+    // The timestamp an event must reach to make the list once it is full.
+    fn cutoff(&mut self) -> Option<DateTime<Utc>> {
+        self.trim();
+        (self.events.len() == self.limit)
+            .then(|| self.events.last().map(|(timestamp, _, _)| *timestamp))
+            .flatten()
+    }
+
+    fn trim(&mut self) {
+        self.events
+            .sort_by_key(|(timestamp, position, _)| Reverse((*timestamp, *position)));
+        self.events.truncate(self.limit);
+    }
+
+    fn into_vec(mut self) -> Vec<Event> {
+        self.trim();
+        self.events.into_iter().map(|(_, _, event)| event).collect()
+    }
+}
+
+/// Iterates over the lines of a file from the end back to the start, without the
+/// trailing newline. Empty lines are skipped.
+pub struct ReverseLines<R> {
+    reader: R,
+    start: u64,
+    pos: u64,
+    buf: Vec<u8>,
+}
+
+impl<R: Read + Seek> ReverseLines<R> {
+    #[cfg(test)]
+    pub fn new(mut reader: R) -> Result<Self> {
+        let end = reader.seek(SeekFrom::End(0))?;
+        Ok(Self::range(reader, 0, end))
+    }
+
+    /// Only the bytes from `start` to `end`, which should fall on line boundaries.
+    pub fn range(reader: R, start: u64, end: u64) -> Self {
+        Self {
+            reader,
+            start,
+            pos: end.max(start),
+            buf: Vec::new(),
+        }
+    }
+
+    fn read_chunk(&mut self) -> std::io::Result<()> {
+        let len = (self.pos - self.start).min(REVERSE_CHUNK);
+        self.pos -= len;
+
+        let mut chunk = vec![0u8; len as usize];
+        self.reader.seek(SeekFrom::Start(self.pos))?;
+        self.reader.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&self.buf);
+        self.buf = chunk;
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> Iterator for ReverseLines<R> {
+    type Item = std::io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(newline) = self.buf.iter().rposition(|&b| b == b'\n') {
+                let line = self.buf.split_off(newline + 1);
+                self.buf.truncate(newline);
+                if line.is_empty() {
+                    continue;
+                }
+                return Some(Ok(line));
+            }
+
+            if self.pos == self.start {
+                return (!self.buf.is_empty()).then(|| Ok(std::mem::take(&mut self.buf)));
+            }
+            if let Err(e) = self.read_chunk() {
+                self.pos = self.start;
+                self.buf.clear();
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+impl EventStore for IndexedStore {
+    fn exists(&self) -> bool {
+        self.log.exists()
+    }
+
+    fn append(&self, event: &Event) -> Result<()> {
+        self.log.append(event)
+    }
+
+    fn append_all(&self, events: &[Event]) -> Result<()> {
+        self.log.append_all(events)
+    }
+
+    fn for_each(&self, filter: &EventFilter, f: &mut dyn FnMut(Event)) -> Result<()> {
+        let _lock = self.log.read_lock()?;
+        let index = self.load_index()?;
+        warn_unparseable(&self.log.path, index.unparseable);
+        let ids = index.candidate_blocks(filter);
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut file = File::open(&self.log.path)?;
+        for id in ids {
+            for event in self.read_block(&mut file, &index.blocks[id])? {
+                if filter.matches(&event) {
+                    f(event);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn last(&self, filter: &EventFilter, pred: &dyn Fn(&Event) -> bool) -> Result<Option<Event>> {
+        Ok(self.newest(filter, 1, pred)?.pop())
+    }
+
+    fn recent(&self, filter: &EventFilter, limit: usize) -> Result<Vec<Event>> {
+        self.newest(filter, limit, &|_| true)
+    }
+
+    fn projects(&self) -> Result<BTreeMap<String, Summary>> {
+        let _lock = self.log.read_lock()?;
+        Ok(self
+            .load_index()?
+            .projects
+            .into_iter()
+            .map(|(name, entry)| (name, entry.summary))
+            .collect())
+    }
+
+    fn sessions(&self) -> Result<BTreeMap<String, Summary>> {
+        let _lock = self.log.read_lock()?;
+        Ok(self
+            .load_index()?
+            .sessions
+            .into_iter()
+            .map(|(id, entry)| (id, entry.summary))
+            .collect())
+    }
+}
+
+fn warn_unparseable(path: &Path, count: usize) {
+    if count > 0 && first_warning(path) {
+        eprintln!(
+            "{} skipped {} unparseable lines in {}. Run `trail migrate --dry-run` to list them.",
+            "warning:".yellow(),
+            count,
+            path.display()
+        );
+    }
+}
+
+/// Whether `path` has not been warned about yet in this process.
+pub(crate) fn first_warning(path: &Path) -> bool {
+    static WARNED: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+    WARNED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(path.to_path_buf())
+}
+
+fn read_head(path: &Path, len: u64) -> Result<String> {
+    let mut buf = Vec::new();
+    File::open(path)?.take(len).read_to_end(&mut buf)?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
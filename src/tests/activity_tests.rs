@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod tests {
+    use crate::activity::{
+        active_time, idle_gap, idle_spans, paused_spans, total_idle, total_paused, work_blocks,
+    };
+    use crate::events::{Event, EventType};
+    use chrono::{Duration, Utc};
+
+    fn idle_event(event_type: EventType, session: &str, minutes_ago: i64) -> Event {
+        Event::new(event_type)
+            .with_timestamp(Utc::now() - Duration::minutes(minutes_ago))
+            .with_session(session.to_string())
+    }
+
+    #[test]
+    fn test_idle_gap_detected_after_timeout() {
+        let now = Utc::now();
+        let last = now - Duration::minutes(25);
+        assert_eq!(idle_gap(last, now, 10), Some((last, now)));
+    }
+
+    #[test]
+    fn test_idle_gap_ignored_within_timeout() {
+        let now = Utc::now();
+        assert_eq!(idle_gap(now - Duration::minutes(3), now, 10), None);
+    }
+
+    #[test]
+    fn test_idle_gap_disabled_with_zero_timeout() {
+        let now = Utc::now();
+        assert_eq!(idle_gap(now - Duration::hours(5), now, 0), None);
+    }
+
+    #[test]
+    fn test_idle_spans_pair_per_session() {
+        let events = [
+            idle_event(EventType::IdleStart, "a", 60),
+            idle_event(EventType::IdleStart, "b", 50),
+            idle_event(EventType::IdleEnd, "a", 30),
+            idle_event(EventType::IdleEnd, "b", 45),
+            idle_event(EventType::IdleEnd, "c", 10),
+        ];
+
+        let spans = idle_spans(&events);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(total_idle(&events).num_minutes(), 35);
+    }
+
+    fn project_event(event_type: EventType, project: &str, minutes_ago: i64) -> Event {
+        idle_event(event_type, "a", minutes_ago).with_project(project.to_string())
+    }
+
+    #[test]
+    fn test_active_time_sums_gaps_within_session() {
+        let events = [
+            project_event(EventType::SessionStart, "alpha", 30),
+            project_event(EventType::command("ls".to_string()), "alpha", 25),
+            project_event(EventType::command("ls".to_string()), "alpha", 20),
+            project_event(EventType::command("ls".to_string()), "beta", 15),
+        ];
+
+        let active = active_time(&events, 10);
+        assert_eq!(active.total.num_minutes(), 15);
+        assert_eq!(active.by_project["alpha"].num_minutes(), 15);
+        assert_eq!(active.by_session["a"].num_minutes(), 15);
+    }
+
+    #[test]
+    fn test_active_time_skips_gaps_over_threshold() {
+        let events = [
+            project_event(EventType::command("ls".to_string()), "alpha", 60),
+            project_event(EventType::command("ls".to_string()), "alpha", 55),
+            project_event(EventType::command("ls".to_string()), "alpha", 20),
+        ];
+
+        let active = active_time(&events, 10);
+        assert_eq!(active.total.num_minutes(), 5);
+    }
+
+    #[test]
+    fn test_active_time_session_end_closes_span() {
+        let events = [
+            project_event(EventType::command("ls".to_string()), "alpha", 30),
+            project_event(EventType::SessionEnd, "alpha", 28),
+            project_event(EventType::SessionStart, "alpha", 25),
+        ];
+
+        let active = active_time(&events, 0);
+        assert_eq!(active.total.num_minutes(), 2);
+    }
+
+    #[test]
+    fn test_active_time_counts_long_running_commands() {
+        let now = Utc::now();
+        let build = Event::new(EventType::Command {
+            cmd: "cargo build".to_string(),
+            exit_code: Some(0),
+            started_at: Some(now - Duration::minutes(40)),
+            finished_at: Some(now),
+            duration_ms: Some(40 * 60 * 1000),
+        })
+        .with_timestamp(now)
+        .with_session("a".to_string())
+        .with_project("alpha".to_string());
+
+        let ls = project_event(EventType::command("ls".to_string()), "alpha", 0)
+            .with_timestamp(now - Duration::minutes(42));
+        let events = [ls, build];
+
+        let active = active_time(&events, 10);
+        assert_eq!(active.total.num_minutes(), 42);
+    }
+
+    #[test]
+    fn test_active_time_pause_closes_span() {
+        let events = [
+            project_event(EventType::command("ls".to_string()), "alpha", 30),
+            project_event(EventType::Paused { until: None }, "alpha", 28),
+            project_event(EventType::Unpaused, "alpha", 25),
+        ];
+
+        let active = active_time(&events, 0);
+        assert_eq!(active.total.num_minutes(), 2);
+    }
+
+    #[test]
+    fn test_paused_spans_end_at_unpause_or_deadline() {
+        let now = Utc::now();
+        let at = |event_type, session: &str, minutes_ago| {
+            idle_event(event_type, session, 0).with_timestamp(now - Duration::minutes(minutes_ago))
+        };
+        let deadline = now - Duration::minutes(40);
+        let events = [
+            at(EventType::Paused { until: None }, "a", 60),
+            at(EventType::Unpaused, "a", 50),
+            at(
+                EventType::Paused {
+                    until: Some(deadline),
+                },
+                "b",
+                45,
+            ),
+            at(EventType::Paused { until: None }, "c", 5),
+        ];
+
+        let spans = paused_spans(&events, now);
+        assert_eq!(spans.len(), 3);
+        assert_eq!((spans[0].1 - spans[0].0).num_minutes(), 10);
+        assert_eq!(spans[1].1, deadline);
+        assert_eq!(spans[2].1, now);
+        assert_eq!(total_paused(&events, now).num_minutes(), 20);
+    }
+
+    #[test]
+    fn test_work_blocks_split_on_idle_and_session_end() {
+        let now = Utc::now();
+        let event = |event_type: EventType, session: &str, minutes_ago: i64| {
+            Event::new(event_type)
+                .with_timestamp(now - Duration::minutes(minutes_ago))
+                .with_session(session.to_string())
+        };
+        let command = |cmd: &str| EventType::command(cmd.to_string());
+        let events = vec![
+            event(EventType::SessionStart, "a", 120),
+            event(command("make"), "a", 119).with_project("api".to_string()),
+            event(command("test"), "a", 115),
+            event(command("deploy"), "a", 60),
+            event(EventType::SessionEnd, "a", 59),
+            event(command("ls"), "b", 30),
+        ];
+
+        let blocks = work_blocks(&events, 10);
+
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].commands, 2);
+        assert!(blocks[0].projects.contains("api"));
+        assert_eq!(blocks[0].end - blocks[0].start, Duration::minutes(4));
+        assert_eq!(blocks[1].commands, 1);
+        assert_eq!(blocks[2].session_id.as_deref(), Some("b"));
+    }
+}
@@ -0,0 +1,267 @@
+#[cfg(test)]
+mod tests {
+    use crate::cli::LogArgs;
+    use crate::config::Config;
+    use crate::daemon::{Daemon, Request, Response};
+    use crate::events::EventType;
+    use crate::logger::Recorder;
+    use crate::session::{Pause, SessionManager, SessionState};
+    use chrono::{DateTime, Duration, Utc};
+    use tempfile::tempdir;
+
+    fn daemon() -> Daemon {
+        let recorder = Recorder::new(Config::default()).unwrap();
+        Daemon::new(recorder, SessionState::default(), Utc::now())
+    }
+
+    fn start(cwd: &str) -> LogArgs {
+        LogArgs {
+            session_start: true,
+            cwd: Some(cwd.to_string()),
+            project: Some("api".to_string()),
+            ..LogArgs::default()
+        }
+    }
+
+    fn command(cmd: &str, session_id: &str, cwd: &str) -> LogArgs {
+        LogArgs {
+            cmd: Some(cmd.to_string()),
+            exit_code: Some(0),
+            session: Some(session_id.to_string()),
+            cwd: Some(cwd.to_string()),
+            project: Some("api".to_string()),
+            ..LogArgs::default()
+        }
+    }
+
+    #[test]
+    fn test_log_buffers_events_and_tracks_session() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path().to_string_lossy().to_string();
+        let mut daemon = daemon();
+
+        let session_id = daemon.log(start(&cwd), Utc::now()).unwrap().unwrap();
+        daemon
+            .log(command("cargo test", &session_id, &cwd), Utc::now())
+            .unwrap();
+
+        let names: Vec<&str> = daemon
+            .pending()
+            .iter()
+            .map(|e| e.event_type.name())
+            .collect();
+        assert_eq!(names, ["project_detected", "session_start", "command"]);
+        assert!(
+            daemon
+                .pending()
+                .iter()
+                .all(|e| e.session_id.as_deref() == Some(session_id.as_str()))
+        );
+
+        let info = &daemon.state().sessions[&session_id];
+        assert_eq!(info.last_cwd.as_deref(), Some(cwd.as_str()));
+        assert_eq!(info.last_project.as_deref(), Some("api"));
+        assert_eq!(
+            daemon.state().current_session_id.as_deref(),
+            Some(session_id.as_str())
+        );
+    }
+
+    #[test]
+    fn test_log_records_idle_gap_since_last_activity() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path().to_string_lossy().to_string();
+        let mut daemon = daemon();
+
+        let earlier = Utc::now() - Duration::hours(1);
+        let session_id = daemon.log(start(&cwd), earlier).unwrap().unwrap();
+        daemon
+            .log(command("ls", &session_id, &cwd), Utc::now())
+            .unwrap();
+
+        let idle: Vec<&EventType> = daemon
+            .pending()
+            .iter()
+            .map(|e| &e.event_type)
+            .filter(|t| matches!(t, EventType::IdleStart | EventType::IdleEnd))
+            .collect();
+        assert_eq!(idle.len(), 2);
+    }
+
+    #[test]
+    fn test_ignored_commands_are_not_buffered() {
+        let mut daemon = daemon();
+        let logged = daemon
+            .log(command(" export TOKEN=x", "s1", "/tmp"), Utc::now())
+            .unwrap();
+
+        assert!(logged.is_none());
+        assert!(daemon.pending().is_empty());
+    }
+
+    #[test]
+    fn test_rebase_keeps_unsaved_changes_and_sees_pauses() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path().to_string_lossy().to_string();
+        let mut daemon = daemon();
+        let session_id = daemon.log(start(&cwd), Utc::now()).unwrap().unwrap();
+
+        let mut disk = SessionState::default();
+        disk.pause(
+            None,
+            Pause {
+                since: Utc::now(),
+                until: None,
+                session_id: None,
+            },
+        );
+        daemon.rebase(disk);
+        assert!(daemon.state().sessions.contains_key(&session_id));
+
+        let before = daemon.pending().len();
+        let logged = daemon
+            .log(command("ls", &session_id, &cwd), Utc::now())
+            .unwrap();
+        assert_eq!(logged.as_deref(), Some(session_id.as_str()));
+        assert_eq!(daemon.pending().len(), before);
+    }
+
+    #[test]
+    fn test_handle_sees_a_quick_pause_and_resume() {
+        let dir = tempdir().unwrap();
+        Config::data_dir_override_for_test(dir.path().to_path_buf());
+        Config::default().save().unwrap();
+        let cwd = dir.path().to_string_lossy().to_string();
+        let write_state = |paused: bool| {
+            let mut state = SessionManager::state().unwrap();
+            if paused {
+                state.pause(
+                    None,
+                    Pause {
+                        since: Utc::now(),
+                        until: None,
+                        session_id: None,
+                    },
+                );
+            } else {
+                state.unpause(None, Utc::now());
+            }
+            let path = Config::state_path().unwrap();
+            std::fs::write(path, serde_json::to_string(&state).unwrap()).unwrap();
+        };
+
+        let mut daemon = Daemon::load().unwrap();
+        let session_id = daemon.handle(Request::Log(start(&cwd))).session_id.unwrap();
+        daemon.flush().unwrap();
+
+        write_state(true);
+        daemon.handle(Request::Log(command("ls", &session_id, &cwd)));
+        assert!(daemon.pending().is_empty());
+
+        write_state(false);
+        daemon.handle(Request::Log(command("ls", &session_id, &cwd)));
+        assert_eq!(daemon.pending().len(), 1);
+    }
+
+    #[test]
+    fn test_reload_sees_a_pause_with_an_unchanged_stamp() {
+        let dir = tempdir().unwrap();
+        Config::data_dir_override_for_test(dir.path().to_path_buf());
+        Config::default().save().unwrap();
+        let cwd = dir.path().to_string_lossy().to_string();
+        let path = Config::state_path().unwrap();
+        let now = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap();
+        let write_pause = |until: DateTime<Utc>| {
+            let mut state = SessionManager::state().unwrap();
+            state.pause(
+                None,
+                Pause {
+                    since: now - Duration::hours(2),
+                    until: Some(until),
+                    session_id: None,
+                },
+            );
+            std::fs::write(&path, serde_json::to_string(&state).unwrap()).unwrap();
+        };
+
+        let mut daemon = Daemon::load().unwrap();
+        let session_id = daemon.handle(Request::Log(start(&cwd))).session_id.unwrap();
+        daemon.flush().unwrap();
+
+        write_pause(now - Duration::hours(1));
+        daemon.handle(Request::Log(command("ls", &session_id, &cwd)));
+        let before = daemon.pending().len();
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+        // Same length and modification time, as a pause and resume within one tick.
+        write_pause(now + Duration::hours(1));
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        daemon.handle(Request::Reload);
+        daemon.handle(Request::Log(command("ls", &session_id, &cwd)));
+        assert_eq!(daemon.pending().len(), before);
+    }
+
+    #[test]
+    fn test_wire_format() {
+        let request = Request::Log(LogArgs {
+            cmd: Some("ls".to_string()),
+            ..LogArgs::default()
+        });
+        assert!(
+            serde_json::to_string(&request)
+                .unwrap()
+                .starts_with(r#"{"log":{"cmd":"ls""#)
+        );
+        assert_eq!(serde_json::to_string(&Request::Stop).unwrap(), r#""stop""#);
+        assert_eq!(serde_json::to_string(&Response::default()).unwrap(), "{}");
+
+        let parsed: Request = serde_json::from_str(r#"{"log":{"cmd":"ls"}}"#).unwrap();
+        assert!(matches!(parsed, Request::Log(LogArgs { cmd: Some(ref cmd), .. }) if cmd == "ls"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_send_log_reports_a_daemon_that_does_not_answer() {
+        use crate::daemon::{send_log, socket_path};
+        use std::io::Write;
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempdir().unwrap();
+        Config::data_dir_override_for_test(dir.path().to_path_buf());
+        let listener = UnixListener::bind(socket_path().unwrap()).unwrap();
+        let args = command("ls", "s1", "/tmp");
+
+        let silent = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            std::thread::sleep(std::time::Duration::from_secs(3));
+            drop(stream);
+            listener
+        });
+        assert!(send_log(&args).is_err());
+        let listener = silent.join().unwrap();
+
+        let garbled = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"not json\n").unwrap();
+            listener
+        });
+        assert!(send_log(&args).is_err());
+        let listener = garbled.join().unwrap();
+
+        let refused = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"{\"error\":\"bad args\"}\n").unwrap();
+        });
+        assert!(send_log(&args).is_err());
+        refused.join().unwrap();
+
+        // Nothing listens on the socket any more, so the caller writes the event itself.
+        assert!(send_log(&args).unwrap().is_none());
+    }
+}
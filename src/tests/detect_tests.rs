@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::detect::{self, MARKER_FILE, ProjectDetector};
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn config(detectors: &[ProjectDetector]) -> Config {
+        Config {
+            project_detectors: detectors.to_vec(),
+            ..Config::default()
+        }
+    }
+
+    fn detect(detectors: &[ProjectDetector], cwd: &Path) -> Option<String> {
+        detect::detect_project(&config(detectors), cwd.to_str().unwrap())
+    }
+
+    #[test]
+    fn test_git_root_names_project() {
+        let dir = tempdir().unwrap();
+        let repo = dir.path().join("website");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(repo.join("src/pages")).unwrap();
+
+        assert_eq!(
+            detect(&[ProjectDetector::Git], &repo.join("src/pages")).as_deref(),
+            Some("website")
+        );
+        assert_eq!(detect(&[ProjectDetector::Git], dir.path()), None);
+    }
+
+    #[test]
+    fn test_manifests_provide_package_names() {
+        let dir = tempdir().unwrap();
+        let cargo = dir.path().join("cargo");
+        let node = dir.path().join("node");
+        let go = dir.path().join("go");
+        for path in [&cargo, &node, &go] {
+            fs::create_dir_all(path.join("sub")).unwrap();
+        }
+
+        fs::write(
+            cargo.join("Cargo.toml"),
+            "[workspace]\nname = \"ignored\"\n\n[package]\nname = \"opstrail\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(node.join("package.json"), r#"{"name": "@team/web"}"#).unwrap();
+        fs::write(go.join("go.mod"), "module github.com/team/api\n\ngo 1.22\n").unwrap();
+
+        let manifest = [ProjectDetector::Manifest];
+        assert_eq!(
+            detect(&manifest, &cargo.join("sub")).as_deref(),
+            Some("opstrail")
+        );
+        assert_eq!(detect(&manifest, &node).as_deref(), Some("@team/web"));
+        assert_eq!(detect(&manifest, &go.join("sub")).as_deref(), Some("api"));
+    }
+
+    #[test]
+    fn test_workspace_manifest_falls_back_to_dir_name() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("mono");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Cargo.toml"), "[workspace]\nmembers = []\n").unwrap();
+
+        assert_eq!(
+            detect(&[ProjectDetector::Manifest], &root).as_deref(),
+            Some("mono")
+        );
+    }
+
+    #[test]
+    fn test_marker_file_names_project() {
+        let dir = tempdir().unwrap();
+        let named = dir.path().join("named");
+        let empty = dir.path().join("empty");
+        fs::create_dir_all(named.join("deep")).unwrap();
+        fs::create_dir_all(&empty).unwrap();
+        fs::write(named.join(MARKER_FILE), "client-x\n").unwrap();
+        fs::write(empty.join(MARKER_FILE), "").unwrap();
+
+        let marker = [ProjectDetector::Marker];
+        assert_eq!(
+            detect(&marker, &named.join("deep")).as_deref(),
+            Some("client-x")
+        );
+        assert_eq!(detect(&marker, &empty).as_deref(), Some("empty"));
+    }
+
+    #[test]
+    fn test_chain_order_decides_between_detectors() {
+        let dir = tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::write(repo.join(MARKER_FILE), "marked").unwrap();
+
+        assert_eq!(
+            detect(&[ProjectDetector::Git, ProjectDetector::Marker], &repo).as_deref(),
+            Some("repo")
+        );
+        assert_eq!(
+            detect(&[ProjectDetector::Marker, ProjectDetector::Git], &repo).as_deref(),
+            Some("marked")
+        );
+        assert_eq!(detect(&[], &repo), None);
+    }
+
+    #[test]
+    fn test_detector_names_round_trip() {
+        for detector in ProjectDetector::all() {
+            let parsed: ProjectDetector = detector.to_string().parse().unwrap();
+            assert_eq!(parsed, detector);
+        }
+        assert!("svn".parse::<ProjectDetector>().is_err());
+    }
+
+    #[test]
+    fn test_missing_detectors_default_to_full_chain() {
+        let config: Config = serde_json::from_str(
+            r#"{"idle_timeout_minutes": 10, "enable_projwarp_integration": true}"#,
+        )
+        .unwrap();
+        assert_eq!(config.project_detectors, ProjectDetector::all());
+    }
+}
@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests {
+    use crate::doctor::check_file;
+    use crate::events::{Event, EventType};
+    use chrono::{Duration, Utc};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn line(session: &str, minutes_ago: i64) -> String {
+        let event = Event::new(EventType::command("ls".to_string()))
+            .with_session(session.to_string())
+            .with_timestamp(Utc::now() - Duration::minutes(minutes_ago));
+        serde_json::to_string(&event).unwrap()
+    }
+
+    #[test]
+    fn test_reports_bad_lines_with_line_numbers() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timeline.jsonl");
+        let mut contents =
+            format!("{}\n{{\"timestamp\":\n{}\n", line("a", 5), line("a", 1)).into_bytes();
+        contents.extend_from_slice(b"\xff\xfe\n");
+        fs::write(&path, contents).unwrap();
+
+        let check = check_file(&path).unwrap();
+
+        assert_eq!(check.events.len(), 2);
+        assert_eq!(check.good.len(), 2);
+        let lines: Vec<usize> = check.bad.iter().map(|bad| bad.line).collect();
+        assert_eq!(lines, vec![2, 4]);
+        assert_eq!(check.bad_lines[0], "{\"timestamp\":");
+        assert!(!check.missing_newline);
+    }
+
+    #[test]
+    fn test_detects_truncated_final_line() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timeline.jsonl");
+        let full = line("a", 1);
+        fs::write(
+            &path,
+            format!("{}\n{}", line("a", 5), &full[..full.len() / 2]),
+        )
+        .unwrap();
+
+        let check = check_file(&path).unwrap();
+
+        assert!(check.missing_newline);
+        assert_eq!(check.bad.len(), 1);
+        assert_eq!(check.bad[0].line, 2);
+    }
+
+    #[test]
+    fn test_out_of_order_is_tracked_per_session() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timeline.jsonl");
+        let lines = [line("a", 10), line("b", 20), line("a", 5), line("a", 8)];
+        fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+        let check = check_file(&path).unwrap();
+
+        assert_eq!(check.out_of_order, vec![4]);
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use crate::events::{Event, EventType};
+
+    fn command_in(cwd: &str, project: Option<&str>) -> Event {
+        let mut event = Event::new(EventType::command("ls".to_string()))
+            .with_cwd(cwd.to_string())
+            .with_session("session-a".to_string());
+        event.project = project.map(str::to_string);
+        event
+    }
+
+    #[test]
+    fn test_no_changes_in_same_directory() {
+        let event = command_in("/work/api", Some("api"));
+        assert!(
+            event
+                .location_changes(Some("/work/api"), Some("api"))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_directory_change_is_detected() {
+        let event = command_in("/tmp", None);
+        let changes = event.location_changes(Some("/home/user"), None);
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].cwd.as_deref(), Some("/tmp"));
+        assert_eq!(changes[0].session_id.as_deref(), Some("session-a"));
+        match &changes[0].event_type {
+            EventType::DirectoryChange { from, to } => {
+                assert_eq!(from, "/home/user");
+                assert_eq!(to, "/tmp");
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_entering_project_records_both_events() {
+        let event = command_in("/work/api/src", Some("api"));
+        let changes = event.location_changes(Some("/home/user"), None);
+
+        assert_eq!(changes.len(), 2);
+        assert!(matches!(
+            changes[0].event_type,
+            EventType::DirectoryChange { .. }
+        ));
+        assert!(matches!(
+            &changes[1].event_type,
+            EventType::ProjectDetected { name } if name == "api"
+        ));
+        assert_eq!(changes[1].project.as_deref(), Some("api"));
+    }
+
+    #[test]
+    fn test_first_event_only_detects_project() {
+        let event = command_in("/work/api", Some("api"));
+        let changes = event.location_changes(None, None);
+
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(
+            changes[0].event_type,
+            EventType::ProjectDetected { .. }
+        ));
+    }
+
+    #[test]
+    fn test_leaving_project_does_not_detect() {
+        let event = command_in("/tmp", None);
+        let changes = event.location_changes(Some("/work/api"), Some("api"));
+
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(
+            changes[0].event_type,
+            EventType::DirectoryChange { .. }
+        ));
+    }
+
+    #[test]
+    fn test_new_events_get_distinct_ids() {
+        let a = Event::new(EventType::SessionStart);
+        let b = Event::new(EventType::SessionStart);
+        assert_ne!(a.id, b.id);
+    }
+
+    #[test]
+    fn test_id_round_trips() {
+        let event = command_in("/work/api", Some("api"));
+        let line = serde_json::to_string(&event).unwrap();
+        let parsed: Event = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.id, event.id);
+    }
+
+    #[test]
+    fn test_legacy_lines_get_deterministic_ids() {
+        let line = r#"{"timestamp":"2024-03-01T10:00:00Z","event_type":{"type":"command","cmd":"ls"},"cwd":"/tmp","project":null,"session_id":"s1"}"#;
+        let other = r#"{"timestamp":"2024-03-01T10:00:01Z","event_type":{"type":"command","cmd":"ls"},"cwd":"/tmp","project":null,"session_id":"s1"}"#;
+
+        let first: Event = serde_json::from_str(line).unwrap();
+        let second: Event = serde_json::from_str(line).unwrap();
+        let third: Event = serde_json::from_str(other).unwrap();
+
+        assert_eq!(first.id, second.id);
+        assert_ne!(first.id, third.id);
+    }
+
+    #[test]
+    fn test_short_id_prefix_matches() {
+        let event = Event::new(EventType::SessionStart);
+        let short = event.short_id();
+
+        assert_eq!(short.len(), crate::events::SHORT_ID_LEN);
+        assert!(event.matches_id(&short));
+        assert!(event.matches_id(&short.to_uppercase()));
+        assert!(event.matches_id(&event.id.to_string()));
+        assert!(!event.matches_id(""));
+        assert!(!Event::new(EventType::SessionStart).matches_id(&event.id.to_string()));
+    }
+}
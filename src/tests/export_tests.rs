@@ -0,0 +1,106 @@
+#[cfg(test)]
+mod tests {
+    use crate::events::{Event, EventType};
+    use crate::export::{to_csv, to_ics, to_json, to_markdown};
+    use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+
+    fn local(hour: u32, minute: u32) -> DateTime<Utc> {
+        Local
+            .with_ymd_and_hms(2024, 3, 1, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn command(cmd: &str, project: Option<&str>, at: DateTime<Utc>) -> Event {
+        let mut event = Event::new(EventType::command(cmd.to_string()))
+            .with_session("s1".to_string())
+            .with_timestamp(at);
+        event.project = project.map(str::to_string);
+        event
+    }
+
+    #[test]
+    fn test_csv_quotes_special_fields() {
+        let events = vec![command("echo \"a,b\"", Some("api"), local(10, 0))];
+        let csv = to_csv(&events);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("id,timestamp,type"));
+        assert!(lines[1].contains(",command,s1,api,,\"echo \"\"a,b\"\"\","));
+    }
+
+    #[test]
+    fn test_json_is_an_array_of_events() {
+        let events = vec![
+            command("ls", None, local(10, 0)),
+            command("pwd", None, local(10, 1)),
+        ];
+        let parsed: Vec<Event> = serde_json::from_str(&to_json(&events).unwrap()).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].id, events[1].id);
+    }
+
+    #[test]
+    fn test_markdown_groups_by_day_and_project() {
+        let events = vec![
+            command("cargo build", Some("api"), local(10, 0)),
+            command("ls", None, local(10, 2)),
+            Event::new(EventType::Note {
+                text: "shipped".to_string(),
+            })
+            .with_session("s1".to_string())
+            .with_project("api".to_string())
+            .with_timestamp(local(10, 5)),
+            command("echo `date`", Some("web"), local(10, 7)),
+        ];
+        let md = to_markdown(&events, 10);
+
+        assert!(md.contains("## Friday, 2024-03-01"));
+        let api = md.find("### api").unwrap();
+        let web = md.find("### web").unwrap();
+        let none = md.find("### (no project)").unwrap();
+        assert!(api < web && web < none);
+        assert!(md.contains("- 10:00 `cargo build`"));
+        assert!(md.contains("- 10:05 **Note:** shipped"));
+        assert!(md.contains("`` echo `date` ``"));
+    }
+
+    #[test]
+    fn test_ics_has_one_event_per_work_block() {
+        let events = vec![
+            Event::new(EventType::SessionStart)
+                .with_session("s1".to_string())
+                .with_timestamp(local(9, 0)),
+            command("make", Some("api"), local(9, 1)),
+            command("make test", Some("api"), local(9, 5)),
+            command("deploy", Some("web"), local(11, 0)),
+        ];
+        let ics = to_ics(&events, 10, Utc::now());
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("SUMMARY:api\r\n"));
+        assert!(ics.contains("DESCRIPTION:2 commands\r\n"));
+        assert!(ics.contains(&format!("DTSTART:{}", local(9, 1).format("%Y%m%dT%H%M%SZ"))));
+        assert!(ics.contains(&format!(
+            "DTEND:{}",
+            (local(11, 0) + Duration::minutes(1)).format("%Y%m%dT%H%M%SZ")
+        )));
+    }
+
+    #[test]
+    fn test_ics_folds_long_lines() {
+        let name = "p".repeat(100);
+        let events = vec![command("make", Some(&name), local(9, 0))];
+        let ics = to_ics(&events, 10, Utc::now());
+
+        assert!(ics.split("\r\n").all(|line| line.len() <= 75));
+        assert!(
+            ics.replace("\r\n ", "")
+                .contains(&format!("SUMMARY:{}", name))
+        );
+    }
+}
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use crate::cli::{FilterArgs, ForgetArgs};
+    use crate::config::Config;
+    use crate::events::{Event, EventType};
+    use crate::forget::{ForgetCriteria, forget, sweep_file};
+    use crate::segments;
+    use chrono::{Duration, Utc};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn args(query: Option<&str>) -> ForgetArgs {
+        ForgetArgs {
+            query: query.map(str::to_string),
+            id: Vec::new(),
+            filter: FilterArgs::default(),
+            dry_run: false,
+            yes: true,
+        }
+    }
+
+    fn line(cmd: &str, project: &str, hours_ago: i64) -> String {
+        let event = Event::new(EventType::command(cmd.to_string()))
+            .with_project(project.to_string())
+            .with_timestamp(Utc::now() - Duration::hours(hours_ago));
+        serde_json::to_string(&event).unwrap()
+    }
+
+    fn write_lines(path: &std::path::Path, lines: &[String]) {
+        fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    #[test]
+    fn test_refuses_to_forget_everything() {
+        assert!(ForgetCriteria::from_args(&args(None)).is_err());
+        assert!(ForgetCriteria::from_args(&args(Some(""))).is_err());
+    }
+
+    #[test]
+    fn test_criteria_combine_text_project_and_range() {
+        let criteria = ForgetCriteria::from_args(&ForgetArgs {
+            filter: FilterArgs {
+                project: Some("alpha".to_string()),
+                from: Some("3h".to_string()),
+                ..FilterArgs::default()
+            },
+            ..args(Some("PASS"))
+        })
+        .unwrap();
+
+        let matches = |cmd: &str, project: &str, hours_ago: i64| {
+            let event: Event = serde_json::from_str(&line(cmd, project, hours_ago)).unwrap();
+            criteria.matches(&event)
+        };
+
+        assert!(matches("echo hunter2pass", "alpha", 1));
+        assert!(!matches("echo hunter2pass", "beta", 1));
+        assert!(!matches("echo hunter2pass", "alpha", 5));
+        assert!(!matches("ls", "alpha", 1));
+    }
+
+    #[test]
+    fn test_invalid_date_is_an_error() {
+        let result = ForgetCriteria::from_args(&ForgetArgs {
+            filter: FilterArgs {
+                date: Some("last tuesday".to_string()),
+                ..FilterArgs::default()
+            },
+            ..args(None)
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_sweep_keeps_unparseable_lines() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timeline.jsonl");
+        write_lines(
+            &path,
+            &[
+                line("ls", "alpha", 1),
+                "{not json".to_string(),
+                line("oops secret", "alpha", 1),
+            ],
+        );
+
+        let criteria = ForgetCriteria::from_args(&args(Some("secret"))).unwrap();
+        let sweep = sweep_file(&path, &criteria).unwrap();
+
+        assert_eq!(sweep.forgotten.len(), 1);
+        assert_eq!(sweep.kept.len(), 2);
+        assert_eq!(sweep.kept[1], "{not json");
+    }
+
+    #[test]
+    fn test_forget_removes_from_timeline_and_archives() {
+        let dir = tempdir().unwrap();
+        let month = segments::month(Utc::now() - Duration::hours(1));
+        let timeline = dir.path().join("timeline").join(format!("{}.jsonl", month));
+        let archive = dir.path().join("timeline-archive-20250101000000.jsonl");
+        fs::create_dir_all(timeline.parent().unwrap()).unwrap();
+        write_lines(
+            &timeline,
+            &[line("ls", "alpha", 1), line("mysql -phunter2", "alpha", 1)],
+        );
+        write_lines(
+            &archive,
+            &[
+                line("mysql -phunter2", "alpha", 900),
+                line("pwd", "beta", 900),
+            ],
+        );
+        Config::data_dir_override_for_test(dir.path().to_path_buf());
+
+        forget(ForgetArgs {
+            dry_run: true,
+            ..args(Some("hunter2"))
+        })
+        .unwrap();
+        assert!(fs::read_to_string(&timeline).unwrap().contains("hunter2"));
+
+        forget(args(Some("hunter2"))).unwrap();
+
+        let remaining =
+            fs::read_to_string(&timeline).unwrap() + &fs::read_to_string(&archive).unwrap();
+        assert!(!remaining.contains("hunter2"));
+        assert_eq!(remaining.lines().count(), 2);
+        assert!(fs::read_dir(dir.path()).unwrap().all(|entry| {
+            !entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .ends_with(".tmp")
+        }));
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::IgnoreConfig;
+    use crate::ignore::IgnoreRules;
+
+    fn rules(commands: &[&str], directories: &[&str]) -> IgnoreRules {
+        IgnoreRules::new(&IgnoreConfig {
+            leading_space: true,
+            commands: commands.iter().map(|c| c.to_string()).collect(),
+            directories: directories.iter().map(|d| d.to_string()).collect(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_plain_pattern_matches_program_name() {
+        let rules = rules(&["ls", "clear"], &[]);
+        assert!(rules.ignores_command("ls"));
+        assert!(rules.ignores_command("ls -la /tmp"));
+        assert!(rules.ignores_command("clear"));
+        assert!(!rules.ignores_command("lsblk"));
+        assert!(!rules.ignores_command("cargo ls"));
+    }
+
+    #[test]
+    fn test_glob_matches_whole_command() {
+        let rules = rules(&["git status*", "*secret*"], &[]);
+        assert!(rules.ignores_command("git status -s"));
+        assert!(rules.ignores_command("cat src/secret/file.txt"));
+        assert!(!rules.ignores_command("git stash"));
+    }
+
+    #[test]
+    fn test_regex_rules() {
+        let rules = rules(&["re:^(vim|nvim)\\b", "re:--insecure"], &[]);
+        assert!(rules.ignores_command("nvim notes.md"));
+        assert!(rules.ignores_command("curl --insecure https://x"));
+        assert!(!rules.ignores_command("vimdiff a b"));
+    }
+
+    #[test]
+    fn test_leading_space_is_ignored() {
+        let rules = rules(&[], &[]);
+        assert!(rules.ignores_command(" echo hidden"));
+        assert!(!rules.ignores_command("echo visible"));
+
+        let keep = IgnoreRules::new(&IgnoreConfig {
+            leading_space: false,
+            ..IgnoreConfig::default()
+        })
+        .unwrap();
+        assert!(!keep.ignores_command(" echo visible"));
+    }
+
+    #[test]
+    fn test_directory_globs() {
+        let rules = rules(&[], &["/home/dev/private/**", "/srv/*/secrets"]);
+        assert!(rules.ignores_directory("/home/dev/private"));
+        assert!(rules.ignores_directory("/home/dev/private/bank/2025"));
+        assert!(rules.ignores_directory("/home/dev/private/"));
+        assert!(!rules.ignores_directory("/home/dev/privateer"));
+        assert!(rules.ignores_directory("/srv/app/secrets"));
+        assert!(!rules.ignores_directory("/srv/app/nested/secrets"));
+    }
+
+    #[test]
+    fn test_directory_globs_expand_home() {
+        let home = dirs::home_dir().unwrap();
+        let rules = rules(&[], &["~/private/**"]);
+        let inside = home.join("private").join("taxes");
+        assert!(rules.ignores_directory(&inside.to_string_lossy()));
+        assert!(!rules.ignores_directory("/private/taxes"));
+    }
+
+    #[test]
+    fn test_windows_directories_are_normalized() {
+        let rules = rules(&[], &["C:/Users/dev/private/**"]);
+        assert!(rules.ignores_directory("C:\\Users\\dev\\private\\hr"));
+    }
+
+    #[test]
+    fn test_invalid_regex_is_an_error() {
+        let config = IgnoreConfig {
+            commands: vec!["re:(".to_string()],
+            ..IgnoreConfig::default()
+        };
+        assert!(IgnoreRules::new(&config).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+#[cfg(test)]
+mod tests {
+    use crate::cli::{HistoryFormat, ImportArgs};
+    use crate::config::Config;
+    use crate::events::{Event, EventType};
+    use crate::import::{
+        import_history, merge_lines, parse_bash, parse_fish, parse_powershell, parse_zsh,
+        to_events, untimed_overlap,
+    };
+    use crate::segments::SegmentedStore;
+    use crate::storage::{EventFilter, EventStore};
+    use chrono::{DateTime, Duration, Utc};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap()
+    }
+
+    fn cmd(event: &Event) -> &str {
+        match &event.event_type {
+            EventType::Command { cmd, .. } => cmd,
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_bash_with_and_without_timestamps() {
+        let entries = parse_bash("ls\n#1700000000\ncargo build\n\ngit status\n");
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].timestamp, None);
+        assert_eq!(entries[1].cmd, "cargo build");
+        assert_eq!(entries[1].timestamp, Some(at(1_700_000_000)));
+        assert_eq!(entries[2].timestamp, None);
+    }
+
+    #[test]
+    fn test_parse_zsh_extended_history() {
+        let mut contents =
+            b": 1700000000:3;make test\n: 1700000010:0;echo one\\\ntwo\nplain\n".to_vec();
+        contents.extend_from_slice(b": 1700000020:0;echo \xc4\x83\xa3\n");
+
+        let entries = parse_zsh(&contents);
+
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].cmd, "make test");
+        assert_eq!(entries[0].timestamp, Some(at(1_700_000_000)));
+        assert_eq!(entries[0].elapsed_secs, Some(3));
+        assert_eq!(entries[1].cmd, "echo one\ntwo");
+        assert_eq!(entries[2].cmd, "plain");
+        assert_eq!(entries[2].timestamp, None);
+        assert_eq!(entries[3].cmd, "echo \u{103}");
+    }
+
+    #[test]
+    fn test_parse_fish_history() {
+        let text = "- cmd: echo a\\nb\n  when: 1700000000\n  paths:\n    - b\n- cmd: ls C:\\\\tmp\n  when: 1700000005\n";
+        let entries = parse_fish(text);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].cmd, "echo a\nb");
+        assert_eq!(entries[0].timestamp, Some(at(1_700_000_000)));
+        assert_eq!(entries[1].cmd, "ls C:\\tmp");
+    }
+
+    #[test]
+    fn test_parse_powershell_continuations() {
+        let entries = parse_powershell("Get-ChildItem\nGet-Process |`\n  Sort-Object CPU\n");
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].cmd, "Get-Process |\n  Sort-Object CPU");
+    }
+
+    #[test]
+    fn test_synthesized_timestamps_are_monotonic() {
+        let anchor = at(1_700_000_000);
+        let entries = parse_powershell("a\nb\nc\n");
+        let events = to_events(HistoryFormat::Powershell, &entries, anchor, 0);
+
+        assert!(events.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+        assert!(events.iter().all(|e| e.timestamp < anchor));
+        assert!(
+            events
+                .iter()
+                .all(|e| e.source.as_deref() == Some("import:powershell:untimed"))
+        );
+        assert!(events.iter().all(|e| e.has_synthesized_time()));
+    }
+
+    #[test]
+    fn test_untimed_entries_follow_previous_timestamp() {
+        let entries = parse_bash("#1700000000\nfirst\nsecond\n");
+        let events = to_events(HistoryFormat::Bash, &entries, Utc::now(), 0);
+
+        assert_eq!(events[0].timestamp, at(1_700_000_000));
+        assert_eq!(
+            events[1].timestamp,
+            at(1_700_000_000) + Duration::seconds(1)
+        );
+    }
+
+    #[test]
+    fn test_ids_are_stable_across_imports() {
+        let before = parse_bash("ls\nls\ncd /tmp\n");
+        let after = parse_bash("ls\nls\ncd /tmp\nmake\n");
+
+        let first = to_events(HistoryFormat::Bash, &before, Utc::now(), 0);
+        let second = to_events(
+            HistoryFormat::Bash,
+            &after,
+            Utc::now() + Duration::hours(1),
+            0,
+        );
+
+        assert_ne!(first[0].id, first[1].id);
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!(a.id, b.id);
+        }
+        let zsh = to_events(HistoryFormat::Zsh, &parse_zsh(b"ls\n"), Utc::now(), 0);
+        assert_ne!(zsh[0].id, first[0].id);
+    }
+
+    #[test]
+    fn test_reimporting_a_trimmed_history_only_adds_new_lines() {
+        let before = parse_bash("ls\nmake\nls\ncd /tmp\n");
+        let trimmed = parse_bash("make\nls\ncd /tmp\nls\n");
+        let cmds = |entries: &[crate::import::HistoryEntry]| -> Vec<String> {
+            entries.iter().map(|entry| entry.cmd.clone()).collect()
+        };
+
+        let overlap = untimed_overlap(&cmds(&before), &cmds(&trimmed));
+        assert_eq!(overlap, 3);
+
+        let first = to_events(HistoryFormat::Bash, &before, Utc::now(), 0);
+        let second = to_events(
+            HistoryFormat::Bash,
+            &trimmed,
+            Utc::now(),
+            before.len() - overlap,
+        );
+
+        let known: Vec<_> = first.iter().map(|e| e.id).collect();
+        let new: Vec<&str> = second
+            .iter()
+            .filter(|e| !known.contains(&e.id))
+            .map(cmd)
+            .collect();
+        assert_eq!(new, ["ls"]);
+    }
+
+    #[test]
+    fn test_untimed_overlap_without_shared_lines() {
+        let lines = |cmds: &[&str]| -> Vec<String> { cmds.iter().map(|c| c.to_string()).collect() };
+
+        assert_eq!(untimed_overlap(&[], &lines(&["ls"])), 0);
+        assert_eq!(untimed_overlap(&lines(&["ls"]), &[]), 0);
+        assert_eq!(
+            untimed_overlap(&lines(&["ls", "make"]), &lines(&["pwd", "ls"])),
+            0
+        );
+        assert_eq!(
+            untimed_overlap(
+                &lines(&["ls", "ls", "ls"]),
+                &lines(&["ls", "ls", "ls", "ls"])
+            ),
+            3
+        );
+    }
+
+    #[test]
+    fn test_import_twice_from_a_trimmed_file() {
+        let dir = tempdir().unwrap();
+        Config::data_dir_override_for_test(dir.path().to_path_buf());
+        let history = dir.path().join("history.txt");
+        let import = || {
+            import_history(ImportArgs {
+                format: HistoryFormat::Powershell,
+                path: Some(history.to_string_lossy().to_string()),
+                dry_run: false,
+            })
+            .unwrap();
+            SegmentedStore::open()
+                .unwrap()
+                .scan(&EventFilter::all())
+                .unwrap()
+        };
+
+        fs::write(&history, "ls\nmake\nls\ncd /tmp\n").unwrap();
+        assert_eq!(import().len(), 4);
+
+        fs::write(&history, "make\nls\ncd /tmp\nls\ngit status\n").unwrap();
+        let events = import();
+        let mut cmds: Vec<&str> = events.iter().map(cmd).collect();
+        cmds.sort();
+        assert_eq!(cmds, ["cd /tmp", "git status", "ls", "ls", "ls", "make"]);
+    }
+
+    #[test]
+    fn test_untimed_imports_do_not_count_as_activity() {
+        let now = Utc::now();
+        let entries = parse_bash("ls\nmake\ncargo test\n");
+        let mut events = to_events(HistoryFormat::Bash, &entries, now, 0);
+        let timed = parse_bash(&format!("#{}\nmake\n", now.timestamp()));
+        events.extend(to_events(HistoryFormat::Bash, &timed, now, 0));
+
+        assert!(!events[3].has_synthesized_time());
+        assert_eq!(
+            crate::activity::active_time(&events, 10).total,
+            Duration::zero()
+        );
+        assert_eq!(crate::activity::work_blocks(&events, 10).len(), 1);
+
+        let day = now.with_timezone(&chrono::Local).date_naive();
+        let summary = crate::query::summarize_day(&events, day, 10, now);
+        assert_eq!(summary.events, 1);
+        assert_eq!(summary.commands, 1);
+        let stats = crate::query::compute_stats(&events, day, day, 10);
+        assert_eq!(stats.commands, 1);
+    }
+
+    #[test]
+    fn test_zsh_duration_is_kept() {
+        let entries = parse_zsh(b": 1700000000:3;make test\n");
+        let events = to_events(HistoryFormat::Zsh, &entries, Utc::now(), 0);
+
+        assert_eq!(cmd(&events[0]), "make test");
+        assert_eq!(events[0].timestamp, at(1_700_000_003));
+        match &events[0].event_type {
+            EventType::Command {
+                started_at,
+                duration_ms,
+                ..
+            } => {
+                assert_eq!(*started_at, Some(at(1_700_000_000)));
+                assert_eq!(*duration_ms, Some(3000));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_merge_keeps_timeline_sorted() {
+        let existing = [
+            Event::new(EventType::command("old".to_string())).with_timestamp(at(100)),
+            Event::new(EventType::command("new".to_string())).with_timestamp(at(300)),
+        ]
+        .iter()
+        .map(|e| serde_json::to_string(e).unwrap())
+        .collect::<Vec<_>>()
+        .join("\n");
+        let imported = vec![
+            Event::new(EventType::command("later".to_string())).with_timestamp(at(400)),
+            Event::new(EventType::command("middle".to_string())).with_timestamp(at(200)),
+        ];
+
+        let lines = merge_lines(&format!("{}\nnot json\n", existing), imported).unwrap();
+        let order: Vec<String> = lines
+            .iter()
+            .map(|line| match serde_json::from_str::<Event>(line) {
+                Ok(event) => cmd(&event).to_string(),
+                Err(_) => line.clone(),
+            })
+            .collect();
+
+        assert_eq!(order, vec!["old", "middle", "new", "not json", "later"]);
+    }
+}
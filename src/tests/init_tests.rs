@@ -0,0 +1,198 @@
+#[cfg(test)]
+mod tests {
+    use crate::cli::Shell;
+    use crate::init::script;
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    const TRAIL: &str = "/opt/it's here/trail";
+
+    fn shell_accepts(shell: &str, args: &[&str], script: &str) -> Option<bool> {
+        let mut child = Command::new(shell)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(script.as_bytes())
+            .unwrap();
+        Some(child.wait().unwrap().success())
+    }
+
+    fn shell_output(shell: &str, args: &[&str], script: &str) -> Option<String> {
+        let mut child = Command::new(shell)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(script.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    #[test]
+    fn test_scripts_embed_quoted_binary_path() {
+        let bash = script(Shell::Bash, TRAIL);
+        assert!(bash.contains("OPSTRAIL_TRAIL_PATH='/opt/it'\\''s here/trail'"));
+
+        let powershell = script(Shell::Powershell, TRAIL);
+        assert!(powershell.contains("$global:OpsTrail_TrailPath = '/opt/it''s here/trail'"));
+
+        let fish = script(Shell::Fish, TRAIL);
+        assert!(fish.contains("set -gx OPSTRAIL_TRAIL_PATH '/opt/it\\'s here/trail'"));
+
+        let nushell = script(Shell::Nushell, "C:\\Tools\\\"trail\".exe");
+        assert!(nushell.contains(r#"$env.OPSTRAIL_TRAIL_PATH = "C:\\Tools\\\"trail\".exe""#));
+
+        for shell in [
+            Shell::Bash,
+            Shell::Zsh,
+            Shell::Fish,
+            Shell::Powershell,
+            Shell::Nushell,
+        ] {
+            let script = script(shell, TRAIL);
+            assert!(!script.contains("__OPSTRAIL_BIN__"));
+            assert!(script.starts_with(&format!("# OpsTrail shell integration for {},", shell)));
+        }
+    }
+
+    #[test]
+    fn test_posix_scripts_cover_logging_and_wrappers() {
+        for shell in [Shell::Bash, Shell::Zsh] {
+            let script = script(shell, TRAIL);
+            for expected in [
+                "log --session-start --print-session-id",
+                "log --session-end",
+                "--exit-code \"$exit_code\"",
+                "--started-at \"$started_at\" --finished-at \"$finished_at\"",
+                "resume --format ndjson",
+                "back)",
+                "resume)",
+            ] {
+                assert!(script.contains(expected), "{} missing {}", shell, expected);
+            }
+        }
+
+        let bash = script(Shell::Bash, TRAIL);
+        assert!(bash.contains("trap opstrail_preexec DEBUG"));
+        assert!(bash.contains("trap opstrail_bash_exit EXIT"));
+        assert!(bash.contains("$BASH_COMMAND"));
+        assert!(!bash.contains("builtin history"));
+        assert!(bash.contains("PROMPT_COMMAND="));
+        assert!(!bash.contains("add-zsh-hook"));
+
+        let zsh = script(Shell::Zsh, TRAIL);
+        assert!(zsh.contains("add-zsh-hook preexec opstrail_preexec"));
+        assert!(zsh.contains("add-zsh-hook zshexit opstrail_exit"));
+        assert!(!zsh.contains("trap "));
+        assert!(!zsh.contains("PROMPT_COMMAND"));
+    }
+
+    #[test]
+    fn test_powershell_script_covers_logging_and_wrappers() {
+        let script = script(Shell::Powershell, TRAIL);
+        for expected in [
+            "log --session-start --print-session-id",
+            "log --session-end",
+            "--exit-code $exitCode --started-at $startedAt --finished-at $finishedAt",
+            "function global:prompt",
+            "PowerShell.Exiting",
+            "resume --format ndjson",
+            "function global:trail-back",
+            "function global:trail-resume",
+        ] {
+            assert!(script.contains(expected), "powershell missing {}", expected);
+        }
+    }
+
+    #[test]
+    fn test_fish_script_covers_logging_and_wrappers() {
+        let script = script(Shell::Fish, TRAIL);
+        for expected in [
+            "log --session-start --print-session-id",
+            "log --session-end",
+            "--on-event fish_preexec",
+            "--on-event fish_postexec",
+            "--on-event fish_exit",
+            "--exit-code $exit_code",
+            "--duration-ms $CMD_DURATION",
+            "resume --format ndjson",
+            "case back",
+            "case resume",
+        ] {
+            assert!(script.contains(expected), "fish missing {}", expected);
+        }
+    }
+
+    #[test]
+    fn test_nushell_script_covers_logging_and_wrappers() {
+        let script = script(Shell::Nushell, TRAIL);
+        for expected in [
+            "log --session-start --print-session-id",
+            "hooks.pre_execution",
+            "hooks.pre_prompt",
+            "(commandline)",
+            "--exit-code $exit_code --duration-ms $duration",
+            "$env.LAST_EXIT_CODE",
+            "$env.CMD_DURATION_MS",
+            "resume --format ndjson",
+            "def --env --wrapped trail",
+            "$subcommand == \"back\"",
+            "$subcommand == \"resume\"",
+        ] {
+            assert!(script.contains(expected), "nushell missing {}", expected);
+        }
+    }
+
+    #[test]
+    fn test_bash_script_parses() {
+        let script = script(Shell::Bash, TRAIL);
+        if let Some(ok) = shell_accepts("bash", &["-n"], &script) {
+            assert!(ok);
+        }
+    }
+
+    #[test]
+    fn test_bash_script_keeps_existing_traps() {
+        let prelude = "prior_debug=0\n\
+            trap 'prior_debug=$((prior_debug + 1))' DEBUG\n\
+            trap 'echo prior exit' EXIT\n";
+        let check = "prior_debug=0\ntrue\necho \"prior debug $prior_debug\"\n";
+        let script = format!("{}{}{}", prelude, script(Shell::Bash, "/bin/true"), check);
+
+        let Some(output) = shell_output("bash", &[], &script) else {
+            return;
+        };
+        assert!(output.contains("prior debug 2"), "{}", output);
+        assert!(output.ends_with("prior exit\n"), "{}", output);
+    }
+
+    #[test]
+    fn test_zsh_script_parses() {
+        let script = script(Shell::Zsh, TRAIL);
+        if let Some(ok) = shell_accepts("zsh", &["-n"], &script) {
+            assert!(ok);
+        }
+    }
+
+    #[test]
+    fn test_fish_script_parses() {
+        let script = script(Shell::Fish, TRAIL);
+        if let Some(ok) = shell_accepts("fish", &["--no-execute"], &script) {
+            assert!(ok);
+        }
+    }
+}
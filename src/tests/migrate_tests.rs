@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod tests {
+    use crate::cli::MigrateArgs;
+    use crate::config::Config;
+    use crate::events::{Event, EventType, SCHEMA_VERSION};
+    use crate::migrate::{migrate, migrate_file, schema_version, upgrade};
+    use serde_json::{Value, json};
+    use std::fs;
+    use tempfile::tempdir;
+
+    const LEGACY: &str = r#"{"timestamp":"2024-03-01T10:00:00Z","event_type":{"type":"command","cmd":"ls"},"cwd":"/tmp","project":null,"session_id":"s1"}"#;
+
+    #[test]
+    fn test_upgrade_adds_schema_and_id() {
+        let mut value: Value = serde_json::from_str(LEGACY).unwrap();
+        assert_eq!(schema_version(&value), 1);
+
+        upgrade(&mut value).unwrap();
+
+        assert_eq!(schema_version(&value), SCHEMA_VERSION);
+        assert!(value["id"].is_string());
+    }
+
+    #[test]
+    fn test_upgrade_keeps_existing_id() {
+        let mut value: Value = serde_json::from_str(LEGACY).unwrap();
+        value["id"] = json!("3f2b9c41-7d0e-4a8b-9c55-1e2f3a4b5c6d");
+
+        upgrade(&mut value).unwrap();
+
+        assert_eq!(value["id"], "3f2b9c41-7d0e-4a8b-9c55-1e2f3a4b5c6d");
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let mut value: Value = serde_json::from_str(LEGACY).unwrap();
+        value["schema"] = json!(SCHEMA_VERSION + 1);
+
+        assert!(upgrade(&mut value).is_err());
+        assert!(serde_json::from_value::<Event>(value).is_err());
+    }
+
+    #[test]
+    fn test_new_events_are_written_at_current_schema() {
+        let line = serde_json::to_string(&Event::new(EventType::SessionStart)).unwrap();
+        let value: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(schema_version(&value), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_file_reports_bad_lines() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timeline.jsonl");
+        let current = serde_json::to_string(&Event::new(EventType::SessionStart)).unwrap();
+        fs::write(
+            &path,
+            format!("{}\n{}\n{{\"timestamp\":\"2024-03\n", LEGACY, current),
+        )
+        .unwrap();
+
+        let result = migrate_file(&path).unwrap();
+
+        assert_eq!(result.migrated, 1);
+        assert_eq!(result.lines.len(), 3);
+        assert_eq!(result.lines[1], current);
+        assert_eq!(result.bad.len(), 1);
+        assert_eq!(result.bad[0].line, 3);
+    }
+
+    #[test]
+    fn test_migrate_rewrites_history() {
+        let dir = tempdir().unwrap();
+        let timeline = dir.path().join("timeline.jsonl");
+        let archive = dir.path().join("timeline-archive-20240101000000.jsonl");
+        fs::write(&timeline, format!("{}\nnot json\n", LEGACY)).unwrap();
+        fs::write(&archive, format!("{}\n", LEGACY)).unwrap();
+        Config::data_dir_override_for_test(dir.path().to_path_buf());
+
+        migrate(MigrateArgs { dry_run: true }).unwrap();
+        assert!(!fs::read_to_string(&timeline).unwrap().contains("schema"));
+
+        migrate(MigrateArgs { dry_run: false }).unwrap();
+
+        assert!(!timeline.exists());
+        let contents = fs::read_to_string(dir.path().join("timeline/2024-03.jsonl")).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines[1], "not json");
+        let value: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(schema_version(&value), SCHEMA_VERSION);
+
+        let before: Event = serde_json::from_str(LEGACY).unwrap();
+        let after: Event = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(before.id, after.id);
+
+        let archived = fs::read_to_string(&archive).unwrap();
+        assert!(archived.contains(&format!("\"schema\":{}", SCHEMA_VERSION)));
+    }
+}
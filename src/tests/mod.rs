@@ -1,4 +1,19 @@
+pub mod activity_tests;
+pub mod daemon_tests;
+pub mod detect_tests;
+pub mod doctor_tests;
+pub mod events_tests;
+pub mod export_tests;
+pub mod forget_tests;
+pub mod ignore_tests;
+pub mod import_tests;
+pub mod init_tests;
+pub mod migrate_tests;
+pub mod projwarp_tests;
 pub mod pruner_tests;
 pub mod query_tests;
+pub mod redact_tests;
+pub mod segments_tests;
 pub mod session_tests;
+pub mod storage_tests;
 pub mod utils_tests;
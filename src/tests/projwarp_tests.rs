@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod tests {
+    use crate::projwarp::{ProjWarpConfig, ProjectResolver};
+    use tempfile::tempdir;
+
+    fn resolver(projects: &[(&str, &str)]) -> ProjectResolver {
+        let config = ProjWarpConfig {
+            projects: projects
+                .iter()
+                .map(|(alias, path)| (alias.to_string(), path.to_string()))
+                .collect(),
+        };
+        ProjectResolver::new(&config)
+    }
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let resolver = resolver(&[
+            ("mono", "/work/mono"),
+            ("api", "/work/mono/packages/api"),
+            ("web", "/work/mono/packages/web"),
+        ]);
+
+        assert_eq!(
+            resolver.resolve("/work/mono/packages/api/src").as_deref(),
+            Some("api")
+        );
+        assert_eq!(
+            resolver.resolve("/work/mono/packages/web").as_deref(),
+            Some("web")
+        );
+        assert_eq!(resolver.resolve("/work/mono/docs").as_deref(), Some("mono"));
+        assert_eq!(resolver.resolve("/work/other"), None);
+    }
+
+    #[test]
+    fn test_resolution_is_deterministic() {
+        for _ in 0..20 {
+            let resolver = resolver(&[("b", "/work/shared"), ("a", "/work/shared")]);
+            assert_eq!(resolver.resolve("/work/shared/x").as_deref(), Some("a"));
+        }
+    }
+
+    #[test]
+    fn test_prefix_must_end_at_separator() {
+        let resolver = resolver(&[("app", "/work/app")]);
+        assert_eq!(resolver.resolve("/work/application"), None);
+        assert_eq!(resolver.resolve("/work/app").as_deref(), Some("app"));
+    }
+
+    #[test]
+    fn test_trailing_slashes_are_ignored() {
+        let resolver = resolver(&[("app", "/work/app/")]);
+        assert_eq!(resolver.resolve("/work/app").as_deref(), Some("app"));
+        assert_eq!(resolver.resolve("/work/app//").as_deref(), Some("app"));
+        assert_eq!(resolver.resolve("/work/app/src/").as_deref(), Some("app"));
+    }
+
+    #[test]
+    fn test_windows_paths_match_case_insensitively() {
+        let resolver = resolver(&[("app", "C:\\Users\\Dev\\App")]);
+        assert_eq!(
+            resolver.resolve("c:\\users\\dev\\app\\src").as_deref(),
+            Some("app")
+        );
+        assert_eq!(
+            resolver.resolve("C:/Users/Dev/App/").as_deref(),
+            Some("app")
+        );
+        assert_eq!(resolver.resolve("D:\\Users\\Dev\\App"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_paths_resolve_to_target_project() {
+        let dir = tempdir().unwrap();
+        let real = dir.path().join("real");
+        std::fs::create_dir_all(real.join("src")).unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let by_real = resolver(&[("app", real.to_str().unwrap())]);
+        assert_eq!(
+            by_real
+                .resolve(link.join("src").to_str().unwrap())
+                .as_deref(),
+            Some("app")
+        );
+
+        let by_link = resolver(&[("app", link.to_str().unwrap())]);
+        assert_eq!(
+            by_link
+                .resolve(real.join("src").to_str().unwrap())
+                .as_deref(),
+            Some("app")
+        );
+    }
+}
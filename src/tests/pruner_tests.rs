@@ -2,19 +2,29 @@
 mod tests {
     use crate::cli::PruneArgs;
     use crate::events::{Event, EventType};
-    use crate::pruner::prune;
+    use crate::pruner::{archive_paths, prune};
+    use crate::segments::{self, SegmentedStore};
+    use crate::storage::{EventFilter, EventStore};
     use chrono::{Duration, Utc};
-    use std::io::Write;
-    use tempfile::NamedTempFile;
+    use std::fs;
 
     fn make_event(days_ago: i64) -> String {
-        let mut event = Event::new(EventType::Command {
-            cmd: "cargo build".to_string(),
-        });
+        let mut event = Event::new(EventType::command("cargo build".to_string()));
         event.timestamp = Utc::now() - Duration::days(days_ago);
         serde_json::to_string(&event).unwrap()
     }
 
+    fn seeded(dir: &std::path::Path) -> SegmentedStore {
+        crate::config::Config::data_dir_override_for_test(dir.to_path_buf());
+        let store = SegmentedStore::new(dir.join("timeline"));
+        let events: Vec<Event> = [make_event(100), make_event(5)]
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        store.append_all(&events).unwrap();
+        store
+    }
+
     #[test]
     fn test_event_age_filtering() {
         let old = make_event(100);
@@ -31,21 +41,88 @@ mod tests {
 
     #[test]
     fn test_prune_dry_run_does_not_modify_file() {
-        let mut file = NamedTempFile::new().unwrap();
-        writeln!(file, "{}", make_event(100)).unwrap();
-        writeln!(file, "{}", make_event(5)).unwrap();
-
-        let original = std::fs::read_to_string(file.path()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let store = seeded(dir.path());
+        let paths = store.segment_paths().unwrap();
+        let original: Vec<String> = paths
+            .iter()
+            .map(|path| fs::read_to_string(path).unwrap())
+            .collect();
 
-        let args = PruneArgs {
+        prune(PruneArgs {
             keep_days: 30,
             dry_run: true,
-        };
+        })
+        .unwrap();
 
-        crate::config::Config::timeline_path_override_for_test(file.path().to_path_buf());
-        let _ = prune(args);
-
-        let after = std::fs::read_to_string(file.path()).unwrap();
+        let after: Vec<String> = paths
+            .iter()
+            .map(|path| fs::read_to_string(path).unwrap())
+            .collect();
         assert_eq!(original, after);
     }
+
+    #[test]
+    fn test_prune_keeps_recent_events_and_removes_expired_months() {
+        let dir = tempfile::tempdir().unwrap();
+        let timeline = dir.path().join("timeline.jsonl");
+        let old = make_event(100);
+        let recent = make_event(5);
+        fs::write(&timeline, format!("{}\n\n{}\nnot json\n", old, recent)).unwrap();
+        let archive = dir.path().join("timeline-archive-20250101-000000.jsonl");
+        fs::write(&archive, format!("{}\n", old)).unwrap();
+
+        crate::config::Config::data_dir_override_for_test(dir.path().to_path_buf());
+        prune(PruneArgs {
+            keep_days: 30,
+            dry_run: false,
+        })
+        .unwrap();
+
+        let store = SegmentedStore::new(dir.path().join("timeline"));
+        let recent_month = segments::month(Utc::now() - Duration::days(5));
+        assert_eq!(store.segment_paths().unwrap().len(), 1);
+        assert_eq!(
+            fs::read_to_string(store.segment_path(&recent_month)).unwrap(),
+            format!("{}\nnot json\n", recent)
+        );
+        assert!(
+            !store
+                .segment_path(&segments::month(Utc::now() - Duration::days(100)))
+                .exists()
+        );
+
+        // The archive from an earlier version is left alone and no new one is written.
+        let archives = archive_paths(dir.path()).unwrap();
+        assert_eq!(archives, [archive]);
+        assert!(fs::read_to_string(&archives[0]).unwrap().contains(&old));
+    }
+
+    #[test]
+    fn test_prune_deletes_whole_old_months() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = seeded(dir.path());
+        let old_month = segments::month(Utc::now() - Duration::days(100));
+        assert_eq!(store.segment_paths().unwrap().len(), 2);
+
+        prune(PruneArgs {
+            keep_days: 30,
+            dry_run: false,
+        })
+        .unwrap();
+
+        let paths = store.segment_paths().unwrap();
+        assert_eq!(paths.len(), 1);
+        assert!(!store.segment_path(&old_month).exists());
+        assert_eq!(store.scan(&EventFilter::all()).unwrap().len(), 1);
+        assert!(
+            store
+                .manifest()
+                .unwrap()
+                .segments
+                .iter()
+                .all(|segment| segment.month != old_month)
+        );
+        assert!(archive_paths(dir.path()).unwrap().is_empty());
+    }
 }
@@ -1,12 +1,18 @@
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
+    use crate::cli::{OutputArgs, StatsArgs};
     use crate::events::{Event, EventType};
-    use chrono::{Duration, Utc};
+    use crate::query::{
+        compute_stats, project_activity, resume_point, search_events, stats_period, summarize_day,
+    };
+    use crate::storage::{EventFilter, EventStore, IndexedStore, Summary};
+    use chrono::{Duration, NaiveDate, Utc};
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
 
     fn make_command_event(cmd: &str, cwd: &str, days_ago: i64) -> Event {
-        let mut event = Event::new(EventType::Command {
-            cmd: cmd.to_string(),
-        });
+        let mut event = Event::new(EventType::command(cmd.to_string()));
         event.timestamp = Utc::now() - Duration::days(days_ago);
         event.cwd = Some(cwd.to_string());
         event
@@ -37,13 +43,13 @@ mod tests {
         let matched: Vec<&Event> = events
             .iter()
             .filter(|e| match &e.event_type {
-                EventType::Command { cmd } => cmd.to_lowercase().contains(query),
+                EventType::Command { cmd, .. } => cmd.to_lowercase().contains(query),
                 _ => false,
             })
             .collect();
 
         assert_eq!(matched.len(), 1);
-        if let EventType::Command { cmd } = &matched[0].event_type {
+        if let EventType::Command { cmd, .. } = &matched[0].event_type {
             assert_eq!(cmd, "cargo build");
         }
     }
@@ -56,7 +62,7 @@ mod tests {
         let matched: Vec<&Event> = events
             .iter()
             .filter(|e| match &e.event_type {
-                EventType::Command { cmd } => cmd.to_lowercase().contains(query),
+                EventType::Command { cmd, .. } => cmd.to_lowercase().contains(query),
                 _ => false,
             })
             .collect();
@@ -89,4 +95,147 @@ mod tests {
         assert!(last.is_some());
         assert_eq!(last.unwrap().cwd.as_deref(), Some("/home/user/new"));
     }
+
+    #[test]
+    fn test_resume_reads_only_the_newest_segment() {
+        let dir = tempdir().unwrap();
+        let store = crate::segments::SegmentedStore::new(dir.path().join("timeline"));
+        for days_ago in [150, 120, 90, 60, 30, 0] {
+            store
+                .append(&make_command_event(
+                    "cargo test",
+                    "/home/user/api",
+                    days_ago,
+                ))
+                .unwrap();
+        }
+
+        let point = resume_point(&store).unwrap().unwrap();
+
+        assert_eq!(point.cwd, "/home/user/api");
+        assert_eq!(point.last_command.as_deref(), Some("cargo test"));
+        assert!(point.paused_at.is_none());
+        assert_eq!(store.segments_read(), 3);
+    }
+
+    #[test]
+    fn test_stats_period_parses_explicit_range() {
+        let args = StatsArgs {
+            from: Some("2024-03-01".to_string()),
+            to: Some("2024-03-10".to_string()),
+            week: false,
+            month: false,
+            output: OutputArgs::default(),
+        };
+        let today = NaiveDate::from_ymd_opt(2024, 3, 20).unwrap();
+
+        let (from, to) = stats_period(&args, today).unwrap();
+        assert_eq!(from, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+        assert_eq!(to, NaiveDate::from_ymd_opt(2024, 3, 10).unwrap());
+
+        let args = StatsArgs {
+            from: Some("March".to_string()),
+            ..args
+        };
+        assert!(stats_period(&args, today).is_err());
+    }
+
+    #[test]
+    fn test_compute_stats_counts_commands_and_failures() {
+        let mut failed = Event::new(EventType::Command {
+            cmd: "cargo test".to_string(),
+            exit_code: Some(101),
+            started_at: None,
+            finished_at: None,
+            duration_ms: Some(4000),
+        });
+        failed.project = Some("opstrail".to_string());
+        let events = [
+            make_command_event("cargo build", "/home/user/project", 0),
+            make_command_event("git status", "/home/user/project", 0),
+            failed,
+        ];
+        let today = chrono::Local::now().date_naive();
+
+        let stats = compute_stats(&events, today, today, 30);
+        assert_eq!(stats.events, 3);
+        assert_eq!(stats.commands, 3);
+        assert_eq!(stats.failed_commands, 1);
+        assert_eq!(stats.top_commands[0].command, "cargo");
+        assert_eq!(stats.top_commands[0].count, 2);
+        assert_eq!(stats.slowest.len(), 1);
+        assert_eq!(stats.slowest[0].duration_ms, 4000);
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["failed_commands"], 1);
+        assert_eq!(json["top_commands"][0]["command"], "cargo");
+    }
+
+    #[test]
+    fn test_summarize_day_reports_active_projects() {
+        let now = Utc::now();
+        let event = |cmd: &str, project: &str, minutes_ago: i64| {
+            Event::new(EventType::command(cmd.to_string()))
+                .with_timestamp(now - Duration::minutes(minutes_ago))
+                .with_project(project.to_string())
+                .with_session("s1".to_string())
+        };
+        let events = [
+            Event::new(EventType::SessionStart)
+                .with_timestamp(now - Duration::minutes(20))
+                .with_session("s1".to_string()),
+            event("cargo build", "api", 15),
+            event("npm test", "web", 5),
+        ];
+
+        let summary = summarize_day(&events, now.date_naive(), 30, now);
+        assert_eq!(summary.events, 3);
+        assert_eq!(summary.commands, 2);
+        assert_eq!(summary.projects.len(), 2);
+        assert!(summary.active_secs > 0);
+        assert!(summary.tracking.is_none());
+    }
+
+    #[test]
+    fn test_search_events_keeps_matches_only() {
+        let dir = tempdir().unwrap();
+        let store = IndexedStore::new(dir.path().join("timeline.jsonl"));
+        store
+            .append_all(&[
+                make_command_event("cargo build", "/a", 0),
+                make_command_event("git status", "/a", 0),
+                Event::new(EventType::Note {
+                    text: "Cargo lock conflict".to_string(),
+                }),
+            ])
+            .unwrap();
+
+        let results = search_events(&store, &EventFilter::all(), "cargo").unwrap();
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn test_project_activity_sorted_by_events() {
+        let mut projects = BTreeMap::new();
+        projects.insert(
+            "small".to_string(),
+            Summary {
+                events: 2,
+                ..Summary::default()
+            },
+        );
+        projects.insert(
+            "big".to_string(),
+            Summary {
+                events: 9,
+                last_cwd: Some("/src/big".to_string()),
+                ..Summary::default()
+            },
+        );
+
+        let activity = project_activity(projects);
+        assert_eq!(activity[0].project, "big");
+        assert_eq!(activity[0].last_cwd.as_deref(), Some("/src/big"));
+        assert_eq!(activity[1].project, "small");
+    }
 }
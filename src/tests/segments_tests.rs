@@ -0,0 +1,222 @@
+#[cfg(test)]
+mod tests {
+    use crate::events::{Event, EventType};
+    use crate::segments::{Manifest, SegmentedStore};
+    use crate::storage::{EventFilter, EventStore};
+    use chrono::{DateTime, TimeZone, Utc};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn at(month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, month, day, 12, 0, 0).unwrap()
+    }
+
+    fn make_event(cmd: &str, project: &str, timestamp: DateTime<Utc>) -> Event {
+        Event::new(EventType::command(cmd.to_string()))
+            .with_cwd(format!("/home/user/{}/{}", project, cmd))
+            .with_project(project.to_string())
+            .with_timestamp(timestamp)
+    }
+
+    fn seeded_store(dir: &std::path::Path) -> SegmentedStore {
+        let store = SegmentedStore::new(dir.join("timeline"));
+        store
+            .append_all(&[
+                make_event("a", "alpha", at(9, 3)),
+                make_event("b", "alpha", at(10, 1)),
+                make_event("c", "beta", at(10, 20)),
+                make_event("d", "alpha", at(11, 5)),
+            ])
+            .unwrap();
+        store
+    }
+
+    fn cmds(events: &[Event]) -> Vec<String> {
+        events
+            .iter()
+            .map(|event| match event.event_type {
+                EventType::Command { ref cmd, .. } => cmd.clone(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_appends_go_to_the_month_of_each_event() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+
+        let names: Vec<String> = store
+            .segment_paths()
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["2025-09.jsonl", "2025-10.jsonl", "2025-11.jsonl"]);
+
+        let manifest = store.manifest().unwrap();
+        let october = &manifest.segments[1];
+        assert_eq!(october.month, "2025-10");
+        assert_eq!(october.events, 2);
+        assert_eq!(october.first, Some(at(10, 1)));
+        assert_eq!(october.last, Some(at(10, 20)));
+        assert!(dir.path().join("timeline/manifest.json").exists());
+    }
+
+    #[test]
+    fn test_queries_span_months() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+
+        let filter = EventFilter::between(at(9, 10), at(11, 1));
+        assert_eq!(cmds(&store.scan(&filter).unwrap()), ["b", "c"]);
+        assert_eq!(
+            cmds(&store.scan(&EventFilter::all()).unwrap()),
+            ["a", "b", "c", "d"]
+        );
+
+        assert_eq!(
+            cmds(&store.recent(&EventFilter::all(), 3).unwrap()),
+            ["d", "c", "b"]
+        );
+        let last_alpha = store
+            .last(&EventFilter::until(at(10, 31)), &|e| {
+                e.project.as_deref() == Some("alpha")
+            })
+            .unwrap();
+        assert_eq!(cmds(&[last_alpha.unwrap()]), ["b"]);
+
+        let projects = store.projects().unwrap();
+        assert_eq!(projects["alpha"].events, 3);
+        assert_eq!(projects["alpha"].first, Some(at(9, 3)));
+        assert_eq!(projects["alpha"].last, Some(at(11, 5)));
+        assert_eq!(
+            projects["alpha"].last_cwd.as_deref(),
+            Some("/home/user/alpha/d")
+        );
+    }
+
+    #[test]
+    fn test_manifest_follows_appends_and_rewrites() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+        assert_eq!(store.manifest().unwrap().segments[2].events, 1);
+
+        store.append(&make_event("e", "beta", at(11, 30))).unwrap();
+        let november = store.manifest().unwrap().segments[2].clone();
+        assert_eq!(november.events, 2);
+        assert_eq!(november.last, Some(at(11, 30)));
+
+        let path = store.segment_path("2025-11");
+        let first = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .to_string();
+        let _lock = store.lock().unwrap();
+        store.replace_lines(&path, &[first]).unwrap();
+        drop(_lock);
+
+        let november = store.manifest().unwrap().segments[2].clone();
+        assert_eq!(november.events, 1);
+        assert_eq!(november.last, Some(at(11, 5)));
+    }
+
+    #[test]
+    fn test_remove_segment_drops_the_month() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+        store.manifest().unwrap();
+
+        let _lock = store.lock().unwrap();
+        store.remove_segment("2025-09").unwrap();
+        drop(_lock);
+
+        let months: Vec<String> = store
+            .manifest()
+            .unwrap()
+            .segments
+            .into_iter()
+            .map(|segment| segment.month)
+            .collect();
+        assert_eq!(months, ["2025-10", "2025-11"]);
+        assert!(!dir.path().join("timeline/2025-09.idx").exists());
+    }
+
+    #[test]
+    fn test_split_legacy_moves_every_line() {
+        let dir = tempdir().unwrap();
+        let legacy = dir.path().join("timeline.jsonl");
+        let lines = [
+            "not json".to_string(),
+            serde_json::to_string(&make_event("a", "alpha", at(9, 3))).unwrap(),
+            String::new(),
+            serde_json::to_string(&make_event("b", "alpha", at(10, 1))).unwrap(),
+            "{broken".to_string(),
+        ];
+        fs::write(&legacy, lines.join("\n") + "\n").unwrap();
+        fs::write(dir.path().join("timeline.idx"), "{}").unwrap();
+
+        let store = SegmentedStore::new(dir.path().join("timeline"));
+        store.append(&make_event("c", "beta", at(10, 20))).unwrap();
+
+        assert_eq!(store.split_legacy(&legacy).unwrap(), 4);
+        assert!(!legacy.exists());
+        assert!(!dir.path().join("timeline.idx").exists());
+
+        let september = fs::read_to_string(store.segment_path("2025-09")).unwrap();
+        assert_eq!(september, format!("not json\n{}\n", lines[1]));
+        let october = fs::read_to_string(store.segment_path("2025-10")).unwrap();
+        assert!(october.ends_with(&format!("{}\n{{broken\n", lines[3])));
+        assert_eq!(
+            cmds(&store.scan(&EventFilter::all()).unwrap()),
+            ["a", "c", "b"]
+        );
+
+        assert_eq!(store.split_legacy(&legacy).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_check_manifest_finds_drift_and_rebuild_clears_it() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+        store.for_each(&EventFilter::all(), &mut |_| {}).unwrap();
+        assert!(store.check_manifest().unwrap().is_empty());
+        assert!(store.stale_indexes().unwrap().is_empty());
+
+        let manifest_path = store.dir().join("manifest.json");
+        let mut manifest: Manifest =
+            serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+        manifest.segments[1].last = Some(at(10, 2));
+        fs::write(&manifest_path, serde_json::to_string(&manifest).unwrap()).unwrap();
+        fs::remove_file(store.segment_path("2025-11")).unwrap();
+        store.append(&make_event("e", "beta", at(12, 1))).unwrap();
+
+        let problems = store.check_manifest().unwrap();
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(problems[0].contains("2025-10.jsonl"));
+        assert!(problems[1].contains("2025-11.jsonl"));
+        assert!(problems[2].contains("2025-12.jsonl"));
+        assert_eq!(
+            store.stale_indexes().unwrap(),
+            [store.dir().join("2025-11.idx")]
+        );
+
+        let rebuilt = store.rebuild_manifest().unwrap();
+        let months: Vec<&str> = rebuilt.segments.iter().map(|s| s.month.as_str()).collect();
+        assert_eq!(months, ["2025-09", "2025-10", "2025-12"]);
+        assert!(store.check_manifest().unwrap().is_empty());
+        assert!(store.stale_indexes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_uncounted_appends_are_not_drift() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+        store.manifest().unwrap();
+
+        store.append(&make_event("e", "beta", at(10, 25))).unwrap();
+        assert!(store.check_manifest().unwrap().is_empty());
+    }
+}
@@ -1,6 +1,17 @@
 #[cfg(test)]
 mod tests {
-    use crate::session::SessionManager;
+    use crate::activity::active_time;
+    use crate::cli::LogArgs;
+    use crate::config::Config;
+    use crate::events::{Event, EventType};
+    use crate::logger::Recorder;
+    use crate::session::{Pause, SessionInfo, SessionManager, SessionState, summarize_sessions};
+    use crate::storage::Summary;
+    use chrono::{Duration, Utc};
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+    use tempfile::tempdir;
 
     #[test]
     fn test_session_ids_are_unique() {
@@ -14,4 +25,310 @@ mod tests {
         let id = SessionManager::generate_session_id_pub();
         assert!(uuid::Uuid::parse_str(&id).is_ok());
     }
+
+    #[test]
+    fn test_concurrent_sessions_are_tracked_separately() {
+        let now = Utc::now();
+        let mut state = SessionState::default();
+        state.start("a".to_string(), now - Duration::minutes(10));
+        state.start("b".to_string(), now - Duration::minutes(5));
+        state.touch("a", now);
+
+        assert_eq!(state.sessions.len(), 2);
+        assert_eq!(state.sessions["a"].last_activity, now);
+        assert_eq!(state.current_session_id.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn test_locate_tracks_cwd_and_project_per_session() {
+        let now = Utc::now();
+        let mut state = SessionState::default();
+        state.start("a".to_string(), now);
+        state.start("b".to_string(), now);
+
+        state.locate("a", "/work/api".to_string(), Some("api".to_string()));
+        state.locate("b", "/tmp".to_string(), None);
+        state.locate("missing", "/tmp".to_string(), None);
+
+        assert_eq!(state.sessions["a"].last_cwd.as_deref(), Some("/work/api"));
+        assert_eq!(state.sessions["a"].last_project.as_deref(), Some("api"));
+        assert_eq!(state.sessions["b"].last_cwd.as_deref(), Some("/tmp"));
+        assert_eq!(state.sessions["b"].last_project, None);
+        assert!(!state.sessions.contains_key("missing"));
+    }
+
+    #[test]
+    fn test_ending_current_session_falls_back_to_most_recent() {
+        let now = Utc::now();
+        let mut state = SessionState::default();
+        state.start("a".to_string(), now - Duration::minutes(10));
+        state.start("b".to_string(), now - Duration::minutes(5));
+        state.touch("a", now);
+
+        state.end("b");
+        assert!(!state.sessions.contains_key("b"));
+        assert_eq!(state.current_session_id.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn test_stale_sessions_are_pruned() {
+        let now = Utc::now();
+        let mut state = SessionState::default();
+        state.start("old".to_string(), now - Duration::days(30));
+        state.start("new".to_string(), now);
+
+        assert!(!state.sessions.contains_key("old"));
+        assert!(state.sessions.contains_key("new"));
+    }
+
+    #[test]
+    fn test_legacy_state_file_is_migrated() {
+        let legacy = r#"{
+            "current_session_id": "legacy",
+            "session_start": "2025-11-14T01:00:00Z",
+            "last_activity": "2025-11-14T01:30:00Z"
+        }"#;
+
+        let state = SessionManager::parse_state(legacy).unwrap();
+        assert_eq!(state.current_session_id.as_deref(), Some("legacy"));
+        assert_eq!(
+            state.sessions["legacy"].last_activity.to_rfc3339(),
+            "2025-11-14T01:30:00+00:00"
+        );
+    }
+
+    fn pause(since: chrono::DateTime<Utc>, minutes: Option<i64>) -> Pause {
+        Pause {
+            since,
+            until: minutes.map(|m| since + Duration::minutes(m)),
+            session_id: None,
+        }
+    }
+
+    #[test]
+    fn test_global_pause_applies_to_every_session() {
+        let now = Utc::now();
+        let mut state = SessionState::default();
+        state.start("a".to_string(), now);
+        state.start("b".to_string(), now);
+        state.pause(None, pause(now, None));
+
+        assert!(state.active_pause("a", now).is_some());
+        assert!(state.active_pause("b", now).is_some());
+        assert_eq!(state.unpause(None, now).len(), 1);
+        assert!(state.active_pause("a", now).is_none());
+    }
+
+    #[test]
+    fn test_terminal_pause_only_applies_to_that_session() {
+        let now = Utc::now();
+        let mut state = SessionState::default();
+        state.start("a".to_string(), now);
+        state.start("b".to_string(), now);
+        state.pause(Some("a"), pause(now, None));
+
+        assert!(state.active_pause("a", now).is_some());
+        assert!(state.active_pause("b", now).is_none());
+        assert_eq!(state.active_pauses(now).len(), 1);
+        assert!(state.unpause(Some("b"), now).is_empty());
+        assert_eq!(state.unpause(Some("a"), now).len(), 1);
+    }
+
+    #[test]
+    fn test_timed_pause_expires() {
+        let now = Utc::now();
+        let mut state = SessionState::default();
+        state.start("a".to_string(), now - Duration::hours(2));
+        state.pause(None, pause(now - Duration::hours(2), Some(30)));
+
+        assert!(state.active_pause("a", now - Duration::hours(2)).is_some());
+        assert!(state.active_pause("a", now).is_none());
+
+        state.touch("a", now);
+        assert!(state.paused.is_none());
+    }
+
+    #[test]
+    fn test_rebuild_state_from_events() {
+        let now = Utc::now();
+        let at = |minutes: i64| now - Duration::minutes(minutes);
+        let events = vec![
+            Event::new(EventType::SessionStart)
+                .with_session("a".to_string())
+                .with_timestamp(at(30)),
+            Event::new(EventType::SessionStart)
+                .with_session("b".to_string())
+                .with_timestamp(at(20)),
+            Event::new(EventType::command("make".to_string()))
+                .with_session("a".to_string())
+                .with_cwd("/work/api".to_string())
+                .with_project("api".to_string())
+                .with_timestamp(at(5)),
+            Event::new(EventType::SessionEnd)
+                .with_session("b".to_string())
+                .with_timestamp(at(10)),
+        ];
+
+        let state = SessionState::rebuild(&events, now);
+
+        assert_eq!(state.sessions.len(), 1);
+        assert_eq!(state.current_session_id.as_deref(), Some("a"));
+        let info = &state.sessions["a"];
+        assert_eq!(info.session_start, at(30));
+        assert_eq!(info.last_activity, at(5));
+        assert_eq!(info.last_cwd.as_deref(), Some("/work/api"));
+        assert_eq!(info.last_project.as_deref(), Some("api"));
+    }
+
+    #[test]
+    fn test_repair_closes_orphaned_sessions() {
+        let now = Utc::now();
+        let mut state = SessionState::default();
+        state.start("a".to_string(), now - Duration::minutes(10));
+        state.start("ghost".to_string(), now - Duration::minutes(5));
+        state.start("quiet".to_string(), now - Duration::minutes(1));
+        state.pause(
+            Some("quiet"),
+            Pause {
+                since: now,
+                until: None,
+                session_id: Some("quiet".to_string()),
+            },
+        );
+
+        let known: BTreeSet<String> = ["a".to_string()].into_iter().collect();
+        assert_eq!(state.orphans(&known), vec!["ghost".to_string()]);
+
+        state.current_session_id = Some("gone".to_string());
+        assert!(state.has_dangling_current());
+
+        assert_eq!(state.repair(&known), vec!["ghost".to_string()]);
+        assert_eq!(state.sessions.len(), 2);
+        assert_eq!(state.current_session_id.as_deref(), Some("quiet"));
+    }
+
+    #[test]
+    fn test_summarize_sessions_newest_first_with_overlaps() {
+        let now = Utc::now();
+        let span = |events: usize, start_mins: i64, end_mins: i64| Summary {
+            events,
+            first: Some(now - Duration::minutes(start_mins)),
+            last: Some(now - Duration::minutes(end_mins)),
+            last_cwd: None,
+        };
+
+        let mut sessions = BTreeMap::new();
+        sessions.insert("old".to_string(), span(4, 300, 240));
+        sessions.insert("a".to_string(), span(10, 60, 10));
+        sessions.insert("b".to_string(), span(3, 30, 0));
+
+        let mut open = BTreeMap::new();
+        open.insert(
+            "b".to_string(),
+            SessionInfo {
+                session_start: now,
+                last_activity: now,
+                last_cwd: None,
+                last_project: None,
+                paused: None,
+            },
+        );
+
+        let active = HashMap::from([("a".to_string(), Duration::minutes(50))]);
+        let summaries = summarize_sessions(sessions, &active, &open);
+        let ids: Vec<&str> = summaries.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, ["b", "a", "old"]);
+        assert_eq!(summaries[0].overlaps, 1);
+        assert!(summaries[0].active);
+        assert_eq!(summaries[1].duration_secs, 50 * 60);
+        assert_eq!(summaries[2].duration_secs, 0);
+        assert_eq!(summaries[2].elapsed_secs, 60 * 60);
+        assert_eq!(summaries[2].overlaps, 0);
+        assert!(!summaries[2].active);
+    }
+
+    #[test]
+    fn test_summarize_sessions_leaves_idle_gaps_out_of_duration() {
+        let now = Utc::now();
+        let command = |minutes_ago: i64| {
+            Event::new(EventType::command("ls".to_string()))
+                .with_timestamp(now - Duration::minutes(minutes_ago))
+                .with_session("s".to_string())
+        };
+        let events = [command(90), command(85), command(20), command(15)];
+
+        let mut sessions = BTreeMap::new();
+        sessions.insert(
+            "s".to_string(),
+            Summary {
+                events: events.len(),
+                first: Some(events[0].timestamp),
+                last: Some(events[3].timestamp),
+                last_cwd: None,
+            },
+        );
+
+        let active = active_time(&events, 10);
+        let summaries = summarize_sessions(sessions, &active.by_session, &BTreeMap::new());
+        assert_eq!(summaries[0].duration_secs, 10 * 60);
+        assert_eq!(summaries[0].elapsed_secs, 75 * 60);
+    }
+
+    #[test]
+    fn test_failed_record_leaves_state_unchanged() {
+        let dir = tempdir().unwrap();
+        Config::data_dir_override_for_test(dir.path().to_path_buf());
+        let recorder = Recorder::new(Config::default()).unwrap();
+        let pending = recorder
+            .prepare(LogArgs {
+                cmd: Some("ls".to_string()),
+                exit_code: Some(0),
+                cwd: Some(dir.path().to_string_lossy().to_string()),
+                ..LogArgs::default()
+            })
+            .unwrap()
+            .unwrap();
+
+        let result = SessionManager::record(|state| {
+            let _ = recorder.commit(pending, state, Utc::now());
+            Err(anyhow::anyhow!("disk full"))
+        });
+        assert!(result.is_err());
+        assert!(SessionManager::open_sessions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_records_share_one_new_session() {
+        let dir = tempdir().unwrap();
+        let barrier = Arc::new(Barrier::new(8));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let data_dir = dir.path().to_path_buf();
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    Config::data_dir_override_for_test(data_dir.clone());
+                    let recorder = Recorder::new(Config::default()).unwrap();
+                    let pending = recorder
+                        .prepare(LogArgs {
+                            cmd: Some("ls".to_string()),
+                            exit_code: Some(0),
+                            cwd: Some(data_dir.to_string_lossy().to_string()),
+                            ..LogArgs::default()
+                        })
+                        .unwrap()
+                        .unwrap();
+                    barrier.wait();
+                    SessionManager::record(|state| Ok(recorder.commit(pending, state, Utc::now())))
+                        .unwrap()
+                        .session_id
+                })
+            })
+            .collect();
+
+        let ids: BTreeSet<String> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(ids.len(), 1);
+
+        Config::data_dir_override_for_test(dir.path().to_path_buf());
+        assert_eq!(SessionManager::open_sessions().unwrap().len(), 1);
+    }
 }
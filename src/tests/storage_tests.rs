@@ -0,0 +1,337 @@
+#[cfg(test)]
+mod tests {
+    use crate::events::{Event, EventType};
+    use crate::storage::{
+        EventFilter, EventStore, IndexedStore, JsonlStore, ReverseLines, first_warning,
+    };
+    use chrono::{Duration, Utc};
+    use std::io::Cursor;
+    use tempfile::tempdir;
+
+    fn make_event(cmd: &str, project: &str, minutes_ago: i64) -> Event {
+        let mut event = Event::new(EventType::command(cmd.to_string()))
+            .with_cwd(format!("/home/user/{}", project))
+            .with_project(project.to_string())
+            .with_session("session-a".to_string());
+        event.timestamp = Utc::now() - Duration::minutes(minutes_ago);
+        event
+    }
+
+    fn seeded_store(dir: &std::path::Path) -> IndexedStore {
+        let store = IndexedStore::new(dir.join("timeline.jsonl"));
+        for i in (0..1000).rev() {
+            let project = if i % 2 == 0 { "alpha" } else { "beta" };
+            store
+                .append(&make_event(&format!("cmd {}", i), project, i))
+                .unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn test_scan_limits_to_time_range() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+
+        let now = Utc::now();
+        let filter = EventFilter::between(now - Duration::minutes(100), now);
+        let events = store.scan(&filter).unwrap();
+
+        assert_eq!(events.len(), 100);
+        assert!(events.iter().all(|e| filter.matches(e)));
+    }
+
+    #[test]
+    fn test_scan_by_project_matches_full_scan() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+        let plain = JsonlStore::new(dir.path().join("timeline.jsonl"));
+
+        let filter = EventFilter::all().with_project("beta".to_string());
+        let indexed = store.scan(&filter).unwrap();
+        let scanned = plain.scan(&filter).unwrap();
+
+        assert_eq!(indexed.len(), 500);
+        assert_eq!(indexed.len(), scanned.len());
+    }
+
+    #[test]
+    fn test_scan_by_session() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+        store
+            .append(&make_event("cargo run", "alpha", 0).with_session("session-b".to_string()))
+            .unwrap();
+
+        let filter = EventFilter::all().with_session("session-b".to_string());
+        let events = store.scan(&filter).unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].session_id.as_deref(), Some("session-b"));
+    }
+
+    #[test]
+    fn test_index_picks_up_appended_events() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+        assert_eq!(store.scan(&EventFilter::all()).unwrap().len(), 1000);
+
+        store.append(&make_event("git push", "gamma", 0)).unwrap();
+
+        let gamma = EventFilter::all().with_project("gamma".to_string());
+        assert_eq!(store.scan(&gamma).unwrap().len(), 1);
+        assert_eq!(store.projects().unwrap()["gamma"].events, 1);
+    }
+
+    #[test]
+    fn test_index_rebuilds_after_rewrite() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timeline.jsonl");
+        let store = seeded_store(dir.path());
+        assert_eq!(store.scan(&EventFilter::all()).unwrap().len(), 1000);
+
+        let rewritten = IndexedStore::new(&path);
+        std::fs::remove_file(&path).unwrap();
+        rewritten.append(&make_event("ls", "delta", 5)).unwrap();
+
+        let events = rewritten.scan(&EventFilter::all()).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].project.as_deref(), Some("delta"));
+    }
+
+    #[test]
+    fn test_last_returns_most_recent_match() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+
+        let target = Utc::now() - Duration::minutes(300);
+        let found = store
+            .last(&EventFilter::until(target), &|e| {
+                e.project.as_deref() == Some("beta")
+            })
+            .unwrap()
+            .unwrap();
+
+        assert!(found.timestamp <= target);
+        assert_eq!(found.project.as_deref(), Some("beta"));
+        if let EventType::Command { cmd, .. } = found.event_type {
+            assert_eq!(cmd, "cmd 301");
+        }
+    }
+
+    fn cmd_of(event: &Event) -> &str {
+        match event.event_type {
+            EventType::Command { ref cmd, .. } => cmd,
+            _ => "",
+        }
+    }
+
+    #[test]
+    fn test_recent_reads_newest_first_from_tail() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+        store
+            .append(&make_event("late import", "alpha", 2000))
+            .unwrap();
+
+        let recent = store.recent(&EventFilter::all(), 3).unwrap();
+        let cmds: Vec<&str> = recent.iter().map(cmd_of).collect();
+        assert_eq!(cmds, ["cmd 0", "cmd 1", "cmd 2"]);
+
+        let plain = JsonlStore::new(dir.path().join("timeline.jsonl"));
+        let filter = EventFilter::all().with_project("beta".to_string());
+        let expected: Vec<String> = plain
+            .recent(&filter, 5)
+            .unwrap()
+            .iter()
+            .map(|e| cmd_of(e).to_string())
+            .collect();
+        let indexed: Vec<String> = store
+            .recent(&filter, 5)
+            .unwrap()
+            .iter()
+            .map(|e| cmd_of(e).to_string())
+            .collect();
+        assert_eq!(indexed, expected);
+        assert_eq!(indexed, ["cmd 1", "cmd 3", "cmd 5", "cmd 7", "cmd 9"]);
+
+        let oldest = store.recent(&EventFilter::all(), 2000).unwrap();
+        assert_eq!(oldest.len(), 1001);
+        assert_eq!(cmd_of(&oldest[1000]), "late import");
+    }
+
+    #[test]
+    fn test_last_prefers_later_line_on_equal_timestamps() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timeline.jsonl");
+        let first = make_event("first", "alpha", 5);
+        let mut second = make_event("second", "alpha", 5);
+        second.timestamp = first.timestamp;
+
+        let store = IndexedStore::new(&path);
+        store.append_all(&[first, second]).unwrap();
+
+        let plain = JsonlStore::new(&path);
+        for found in [
+            store.last(&EventFilter::all(), &|_| true).unwrap(),
+            plain.last(&EventFilter::all(), &|_| true).unwrap(),
+        ] {
+            assert_eq!(cmd_of(&found.unwrap()), "second");
+        }
+        assert_eq!(
+            cmd_of(&store.recent(&EventFilter::all(), 1).unwrap()[0]),
+            "second"
+        );
+        assert_eq!(
+            cmd_of(&plain.recent(&EventFilter::all(), 1).unwrap()[0]),
+            "second"
+        );
+    }
+
+    #[test]
+    fn test_reverse_lines() {
+        let long = "x".repeat(200_000);
+        let text = format!("one\n\ntwo\n{}\nthree", long);
+
+        let lines: Vec<Vec<u8>> = ReverseLines::new(Cursor::new(text.as_bytes()))
+            .unwrap()
+            .map(|line| line.unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                b"three".to_vec(),
+                long.as_bytes().to_vec(),
+                b"two".to_vec(),
+                b"one".to_vec()
+            ]
+        );
+
+        let middle: Vec<Vec<u8>> = ReverseLines::range(Cursor::new(text.as_bytes()), 4, 9)
+            .map(|line| line.unwrap())
+            .collect();
+        assert_eq!(middle, [b"two".to_vec()]);
+    }
+
+    #[test]
+    fn test_sessions_summary_spans_all_events() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+
+        let sessions = store.sessions().unwrap();
+        let summary = &sessions["session-a"];
+
+        assert_eq!(summary.events, 1000);
+        assert!(summary.first.unwrap() < summary.last.unwrap());
+    }
+
+    #[test]
+    fn test_legacy_command_lines_still_load() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timeline.jsonl");
+        std::fs::write(
+            &path,
+            "{\"timestamp\":\"2025-11-14T01:33:24Z\",\"event_type\":{\"type\":\"command\",\"cmd\":\"cargo run\"},\"cwd\":null,\"project\":null,\"session_id\":null}\n",
+        )
+        .unwrap();
+
+        let events = IndexedStore::new(&path).scan(&EventFilter::all()).unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            events[0].event_type,
+            EventType::Command {
+                exit_code: None,
+                duration_ms: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_concurrent_appends_keep_every_line() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timeline.jsonl");
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let store = IndexedStore::new(&path);
+                    for i in 0..50 {
+                        store
+                            .append(&make_event(&format!("cmd {} {}", t, i), "alpha", 0))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 400);
+        assert!(
+            contents
+                .lines()
+                .all(|line| serde_json::from_str::<Event>(line).is_ok())
+        );
+    }
+
+    #[test]
+    fn test_append_all_keeps_order() {
+        let dir = tempdir().unwrap();
+        let store = IndexedStore::new(dir.path().join("timeline.jsonl"));
+        let batch: Vec<Event> = (0..3)
+            .map(|i| make_event(&format!("cmd {}", i), "alpha", 3 - i))
+            .collect();
+
+        store.append_all(&batch).unwrap();
+        store.append_all(&[]).unwrap();
+
+        let cmds: Vec<String> = store
+            .scan(&EventFilter::all())
+            .unwrap()
+            .into_iter()
+            .map(|e| match e.event_type {
+                EventType::Command { cmd, .. } => cmd,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(cmds, ["cmd 0", "cmd 1", "cmd 2"]);
+    }
+
+    #[test]
+    fn test_replace_lines_invalidates_index() {
+        let dir = tempdir().unwrap();
+        let store = seeded_store(dir.path());
+        assert_eq!(store.scan(&EventFilter::all()).unwrap().len(), 1000);
+
+        let kept = vec![serde_json::to_string(&make_event("ls", "alpha", 1)).unwrap()];
+        {
+            let _lock = store.lock().unwrap();
+            store.replace_lines(&kept).unwrap();
+        }
+
+        assert_eq!(store.scan(&EventFilter::all()).unwrap().len(), 1);
+        assert_eq!(store.projects().unwrap().len(), 1);
+        assert!(std::fs::read_dir(dir.path()).unwrap().all(|entry| {
+            !entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .ends_with(".tmp")
+        }));
+    }
+
+    #[test]
+    fn test_unparseable_lines_are_reported_once_per_file() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("2026-01.jsonl");
+        let second = dir.path().join("2026-02.jsonl");
+
+        assert!(first_warning(&first));
+        assert!(!first_warning(&first));
+        assert!(first_warning(&second));
+    }
+}
@@ -1,7 +1,12 @@
 #[cfg(test)]
 mod tests {
-    use crate::utils::parse_relative_time;
-    use chrono::Utc;
+    use crate::cli::OutputFormat;
+    use crate::utils::{
+        AtomicWriter, json_document, json_stream, parse_duration, parse_relative_time,
+        parse_timestamp,
+    };
+    use chrono::{TimeZone, Utc};
+    use tempfile::tempdir;
 
     #[test]
     fn test_parse_minutes() {
@@ -57,4 +62,79 @@ mod tests {
         assert!(parse_relative_time("xm").is_err());
         assert!(parse_relative_time("").is_err());
     }
+
+    #[test]
+    fn test_parse_timestamp_epoch_seconds() {
+        let expected = Utc.timestamp_micros(1_731_545_604_250_000).unwrap();
+        assert_eq!(parse_timestamp("1731545604.25").unwrap(), expected);
+        assert_eq!(parse_timestamp("1731545604,250000").unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_timestamp_rfc3339() {
+        let expected = Utc.with_ymd_and_hms(2025, 11, 14, 1, 33, 24).unwrap();
+        assert_eq!(parse_timestamp("2025-11-14T01:33:24Z").unwrap(), expected);
+        assert_eq!(
+            parse_timestamp("2025-11-14T02:33:24.0000000+01:00").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp_invalid() {
+        assert!(parse_timestamp("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(
+            parse_duration("45m").unwrap(),
+            chrono::Duration::minutes(45)
+        );
+        assert_eq!(parse_duration("2h").unwrap(), chrono::Duration::hours(2));
+        assert_eq!(parse_duration("1w").unwrap(), chrono::Duration::weeks(1));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn test_json_stream_formats() {
+        let items = [serde_json::json!({"a": 1}), serde_json::json!({"a": 2})];
+
+        let ndjson = json_stream(OutputFormat::Ndjson, &items).unwrap();
+        assert_eq!(ndjson, "{\"a\":1}\n{\"a\":2}\n");
+
+        let json = json_stream(OutputFormat::Json, &items).unwrap();
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.len(), 2);
+
+        assert_eq!(
+            json_stream::<serde_json::Value>(OutputFormat::Ndjson, &[]).unwrap(),
+            ""
+        );
+        assert_eq!(
+            json_document(OutputFormat::Ndjson, &None::<u8>).unwrap(),
+            "null\n"
+        );
+    }
+
+    #[test]
+    fn test_atomic_writer_replaces_only_on_commit() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timeline.jsonl");
+        std::fs::write(&path, "old\n").unwrap();
+
+        let mut writer = AtomicWriter::create(&path).unwrap();
+        writer.write_line("abandoned").unwrap();
+        drop(writer);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old\n");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let mut writer = AtomicWriter::create(&path).unwrap();
+        writer.write_line("a").unwrap();
+        writer.write_line("b").unwrap();
+        writer.commit().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb\n");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
 }
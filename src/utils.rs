@@ -1,59 +1,70 @@
+use crate::cli::OutputFormat;
 use anyhow::{Result, anyhow};
-use chrono::{DateTime, Duration, Local, Utc};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 
 pub fn parse_relative_time(input: &str) -> Result<DateTime<Utc>> {
     let now = Utc::now();
 
     match input {
         "now" => Ok(now),
-        "today" => {
-            let local = Local::now();
-            let today_start = local.date_naive().and_hms_opt(0, 0, 0).unwrap();
-            Ok(today_start
-                .and_local_timezone(Local)
-                .unwrap()
-                .with_timezone(&Utc))
-        }
+        "today" => Ok(local_day_start(Local::now().date_naive())),
         "yesterday" => {
-            let local = Local::now();
-            let yesterday = local.date_naive().pred_opt().unwrap();
-            let yesterday_start = yesterday.and_hms_opt(0, 0, 0).unwrap();
-            Ok(yesterday_start
-                .and_local_timezone(Local)
-                .unwrap()
-                .with_timezone(&Utc))
+            let yesterday = Local::now().date_naive().pred_opt().unwrap();
+            Ok(local_day_start(yesterday))
         }
         "last-session" => Ok(now - Duration::hours(1)),
-        s if s.ends_with('m') => {
-            let mins: i64 = s
-                .trim_end_matches('m')
-                .parse()
-                .map_err(|_| anyhow!("Invalid time format: {}", s))?;
-            Ok(now - Duration::minutes(mins))
-        }
-        s if s.ends_with('h') => {
-            let hours: i64 = s
-                .trim_end_matches('h')
-                .parse()
-                .map_err(|_| anyhow!("Invalid time format: {}", s))?;
-            Ok(now - Duration::hours(hours))
-        }
-        s if s.ends_with('d') => {
-            let days: i64 = s
-                .trim_end_matches('d')
-                .parse()
-                .map_err(|_| anyhow!("Invalid time format: {}", s))?;
-            Ok(now - Duration::days(days))
-        }
-        s if s.ends_with('w') => {
-            let weeks: i64 = s
-                .trim_end_matches('w')
-                .parse()
-                .map_err(|_| anyhow!("Invalid time format: {}", s))?;
-            Ok(now - Duration::weeks(weeks))
-        }
-        _ => Err(anyhow!("Unrecognized time format: {}", input)),
+        s => Ok(now - parse_duration(s)?),
+    }
+}
+
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let unit = input
+        .chars()
+        .last()
+        .ok_or_else(|| anyhow!("Unrecognized time format: {}", input))?;
+    let make: fn(i64) -> Duration = match unit {
+        'm' => Duration::minutes,
+        'h' => Duration::hours,
+        'd' => Duration::days,
+        'w' => Duration::weeks,
+        _ => return Err(anyhow!("Unrecognized time format: {}", input)),
+    };
+
+    let value: i64 = input[..input.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| anyhow!("Invalid time format: {}", input))?;
+    Ok(make(value))
+}
+
+pub fn parse_time_bound(input: &str, end_of_day: bool) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Ok(if end_of_day {
+            local_day_end(date)
+        } else {
+            local_day_start(date)
+        });
+    }
+
+    parse_timestamp(input).or_else(|_| parse_relative_time(input))
+}
+
+pub fn parse_timestamp(input: &str) -> Result<DateTime<Utc>> {
+    let trimmed = input.trim();
+
+    if let Ok(ts) = DateTime::parse_from_rfc3339(trimmed) {
+        return Ok(ts.with_timezone(&Utc));
     }
+
+    let secs: f64 = trimmed
+        .replace(',', ".")
+        .parse()
+        .map_err(|_| anyhow!("Invalid timestamp: {}", input))?;
+    DateTime::from_timestamp_micros((secs * 1_000_000.0).round() as i64)
+        .ok_or_else(|| anyhow!("Timestamp out of range: {}", input))
 }
 
 pub fn format_duration(duration: Duration) -> String {
@@ -82,3 +93,124 @@ pub fn format_duration(duration: Duration) -> String {
         }
     }
 }
+
+pub fn local_day_start(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_local_timezone(Local)
+        .earliest()
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+pub fn local_day_end(date: NaiveDate) -> DateTime<Utc> {
+    local_day_start(date.succ_opt().unwrap()) - Duration::nanoseconds(1)
+}
+
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    write_atomic_all(&[(path, contents)])
+}
+
+pub fn write_atomic_all(files: &[(&Path, &[u8])]) -> Result<()> {
+    let mut staged = Vec::new();
+
+    let result = (|| -> Result<()> {
+        for (path, contents) in files {
+            let tmp_path = tmp_path(path)?;
+            staged.push(tmp_path.clone());
+
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(contents)?;
+            file.sync_all()?;
+        }
+
+        for ((path, _), tmp_path) in files.iter().zip(&staged) {
+            fs::rename(tmp_path, path)?;
+        }
+        Ok(())
+    })();
+
+    if result.is_err() {
+        for tmp_path in &staged {
+            let _ = fs::remove_file(tmp_path);
+        }
+    }
+
+    result
+}
+
+pub fn json_document<T: Serialize>(format: OutputFormat, value: &T) -> Result<String> {
+    let mut json = match format {
+        OutputFormat::Json => serde_json::to_string_pretty(value)?,
+        _ => serde_json::to_string(value)?,
+    };
+    json.push('\n');
+    Ok(json)
+}
+
+pub fn json_stream<T: Serialize>(format: OutputFormat, items: &[T]) -> Result<String> {
+    if format == OutputFormat::Json {
+        return json_document(format, &items);
+    }
+
+    let mut json = String::new();
+    for item in items {
+        json.push_str(&serde_json::to_string(item)?);
+        json.push('\n');
+    }
+    Ok(json)
+}
+
+/// Writes lines to a temporary file that replaces `path` on [`AtomicWriter::commit`].
+/// The temporary file is removed if the writer is dropped without committing.
+pub struct AtomicWriter {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    file: Option<BufWriter<File>>,
+}
+
+impl AtomicWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        let tmp_path = tmp_path(path)?;
+        let file = BufWriter::new(File::create(&tmp_path)?);
+        Ok(Self {
+            path: path.to_path_buf(),
+            tmp_path,
+            file: Some(file),
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> Result<()> {
+        if let Some(ref mut file) = self.file {
+            file.write_all(line.as_bytes())?;
+            file.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    pub fn commit(mut self) -> Result<()> {
+        if let Some(ref mut file) = self.file {
+            file.flush()?;
+            file.get_ref().sync_all()?;
+            fs::rename(&self.tmp_path, &self.path)?;
+        }
+        self.file = None;
+        Ok(())
+    }
+}
+
+impl Drop for AtomicWriter {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            let _ = fs::remove_file(&self.tmp_path);
+        }
+    }
+}
+
+fn tmp_path(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid path: {}", path.display()))?
+        .to_string_lossy();
+    Ok(path.with_file_name(format!("{}.{}.tmp", file_name, std::process::id())))
+}